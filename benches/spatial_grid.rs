@@ -0,0 +1,93 @@
+//! Compares finding the walls around a body through the `SpatialGrid` with
+//! testing every wall, which is what collision detection did before the
+//! grid. Run with `cargo bench --bench spatial_grid`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use echoed_footsteps::SpatialGrid;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const LEVEL_SIZE: Vec2 = Vec2::new(1200., 800.);
+const WALL_COUNTS: [usize; 3] = [100, 300, 600];
+/// Same cell size the game uses
+const CELL_SIZE: f32 = 64.;
+const QUERIES: usize = 10_000;
+const ROUNDS: usize = 20;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for wall_count in WALL_COUNTS {
+        let walls: Vec<(Entity, Rect)> = (0..wall_count)
+            .map(|i| {
+                let size = Vec2::new(rng.gen_range(16.0..96.), rng.gen_range(16.0..96.));
+                let min = Vec2::new(
+                    rng.gen_range(0.0..LEVEL_SIZE.x),
+                    rng.gen_range(0.0..LEVEL_SIZE.y),
+                );
+                (
+                    Entity::from_raw(i as u32),
+                    Rect::from_corners(min, min + size),
+                )
+            })
+            .collect();
+
+        let grid = SpatialGrid::with_static(CELL_SIZE, walls.iter().copied());
+
+        // Areas a player sized body sweeps through in one step
+        let areas: Vec<Rect> = (0..QUERIES)
+            .map(|_| {
+                let min = Vec2::new(
+                    rng.gen_range(0.0..LEVEL_SIZE.x),
+                    rng.gen_range(0.0..LEVEL_SIZE.y),
+                );
+                Rect::from_corners(min, min + Vec2::new(26., 18.))
+            })
+            .collect();
+
+        let linear = measure(|| {
+            for area in &areas {
+                black_box(
+                    walls
+                        .iter()
+                        .filter(|(_, bounds)| overlaps(*bounds, *area))
+                        .map(|(entity, _)| *entity)
+                        .collect::<Vec<_>>(),
+                );
+            }
+        });
+
+        let grid_time = measure(|| {
+            for area in &areas {
+                black_box(grid.query_static(*area));
+            }
+        });
+
+        println!(
+            "{wall_count:>4} walls: linear scan {:>8.2?} per query, grid {:>8.2?} per query, {:.1}x faster",
+            linear / QUERIES as u32,
+            grid_time / QUERIES as u32,
+            linear.as_secs_f64() / grid_time.as_secs_f64(),
+        );
+    }
+}
+
+/// Same inclusive test the grid does on the walls in the cells it visits
+fn overlaps(a: Rect, b: Rect) -> bool {
+    a.min.x <= b.max.x && a.max.x >= b.min.x && a.min.y <= b.max.y && a.max.y >= b.min.y
+}
+
+/// Fastest of several rounds, the others are mostly noise
+fn measure(mut run: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
@@ -11,8 +11,11 @@ pub struct ActionsPlugin;
 // Actions can then be used as a resource in other systems to act on the player input.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Actions>()
-            .add_system(set_movement_actions.in_set(OnUpdate(GameState::Playing)));
+        app.init_resource::<Actions>().add_system(
+            set_movement_actions
+                .run_if(in_state(GameState::Playing))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
     }
 }
 
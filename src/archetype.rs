@@ -0,0 +1,146 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+
+use crate::components::ColliderShape;
+use crate::config::{PROJECTILE_LIFETIME, PROJECTILE_RANGE, UNIT};
+use crate::enemy::{EchoMode, EchoVariant, FirePattern, ProjectileKind};
+
+pub struct ArchetypePlugin;
+
+/// Loads enemy archetypes from `assets/enemies/*.enemy.ron` and keeps track
+/// of them by name, so LDtk spawners can refer to them. Edited files are
+/// picked up again while the game runs.
+impl Plugin for ArchetypePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<EnemyArchetype>()
+            .init_asset_loader::<EnemyArchetypeLoader>()
+            .init_resource::<EnemyRegistry>()
+            .add_system(update_registry);
+    }
+}
+
+/// Everything that sets one kind of enemy apart from another. Fields left
+/// out of the RON file keep their default.
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "3f6c1b9e-8d2a-4f5e-9b7c-2a1d4e6f8c30"]
+#[serde(default)]
+pub struct EnemyArchetype {
+    pub name: String,
+    /// Sprite colour, the colour of the echo variant if left out
+    pub color: Option<Color>,
+    pub size: (f32, f32),
+    pub shape: ColliderShape,
+    /// Seconds between spotting the player and starting to aim
+    pub reaction_time: f32,
+    /// Seconds the aim line is shown before the shot
+    pub aim_time: f32,
+    /// Seconds after a shot before the player can be spotted again
+    pub cooldown: f32,
+    /// Units per second
+    pub projectile_speed: f32,
+    /// Seconds a projectile flies before it fizzles out
+    pub projectile_lifetime: f32,
+    /// Units a projectile flies before it fizzles out
+    pub projectile_range: f32,
+    pub fire_pattern: FirePattern,
+    pub projectile: ProjectileKind,
+    pub vision_range: f32,
+    /// Full field of view in degrees, enemies see all around without one
+    pub vision_cone: Option<f32>,
+    pub echo_mode: EchoMode,
+    /// Seconds behind the player, a random moment if left out
+    pub echo_delay: Option<f32>,
+    pub echo_variant: EchoVariant,
+    pub echo_speed: f32,
+}
+
+impl Default for EnemyArchetype {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            color: None,
+            size: (UNIT, UNIT),
+            shape: ColliderShape::Capsule,
+            reaction_time: 0.3,
+            aim_time: 0.6,
+            cooldown: 1.2,
+            projectile_speed: 300.,
+            projectile_lifetime: PROJECTILE_LIFETIME,
+            projectile_range: PROJECTILE_RANGE,
+            fire_pattern: FirePattern::default(),
+            projectile: ProjectileKind::default(),
+            vision_range: 1000.,
+            vision_cone: None,
+            echo_mode: EchoMode::default(),
+            echo_delay: None,
+            echo_variant: EchoVariant::default(),
+            echo_speed: 1.,
+        }
+    }
+}
+
+/// Loaded enemy archetypes by name
+#[derive(Resource, Default)]
+pub struct EnemyRegistry {
+    archetypes: HashMap<String, Handle<EnemyArchetype>>,
+}
+
+impl EnemyRegistry {
+    pub fn get<'a>(
+        &self,
+        name: &str,
+        assets: &'a Assets<EnemyArchetype>,
+    ) -> Option<&'a EnemyArchetype> {
+        self.archetypes
+            .get(name)
+            .and_then(|handle| assets.get(handle))
+    }
+}
+
+fn update_registry(
+    mut events: EventReader<AssetEvent<EnemyArchetype>>,
+    assets: Res<Assets<EnemyArchetype>>,
+    mut registry: ResMut<EnemyRegistry>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                // The name might have changed, forget the old one
+                registry.archetypes.retain(|_, other| other != handle);
+
+                if let Some(archetype) = assets.get(handle) {
+                    registry
+                        .archetypes
+                        .insert(archetype.name.clone(), handle.clone_weak());
+                }
+            }
+            AssetEvent::Removed { handle } => {
+                registry.archetypes.retain(|_, other| other != handle);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct EnemyArchetypeLoader;
+
+impl AssetLoader for EnemyArchetypeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let archetype: EnemyArchetype = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(archetype));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemy.ron"]
+    }
+}
@@ -1,3 +1,4 @@
+use crate::enemy::EnemyDied;
 use crate::loading::AudioAssets;
 use crate::player::{FootstepEvent, Player};
 use crate::GameState;
@@ -10,7 +11,7 @@ pub struct InternalAudioPlugin;
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(AudioPlugin)
-            .add_system(on_footstep.in_set(OnUpdate(GameState::Playing)));
+            .add_systems((on_footstep, on_enemy_died).in_set(OnUpdate(GameState::Playing)));
     }
 }
 
@@ -37,3 +38,15 @@ fn on_footstep(
         }
     }
 }
+
+fn on_enemy_died(
+    audio_assets: Res<AudioAssets>,
+    audio: Res<Audio>,
+    mut events: EventReader<EnemyDied>,
+) {
+    // Enemies squashed together die at once, one explosion is enough
+    if !events.is_empty() {
+        events.clear();
+        audio.play(audio_assets.explosion.clone()).with_volume(0.2);
+    }
+}
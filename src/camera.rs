@@ -0,0 +1,149 @@
+use crate::components::{PreviousTranslation, Velocity};
+use crate::config::*;
+use crate::environment::LevelBounds;
+use crate::physics::interpolated_translation;
+use crate::player::Player;
+use crate::post_processing::MainCamera;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+
+pub struct CameraPlugin;
+
+/// This plugin moves the main camera around levels larger than the window
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(start_overview).add_system(
+            follow_player
+                .after(start_overview)
+                .in_set(OnUpdate(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component, Default)]
+pub struct CameraController {
+    /// Point the camera is easing towards
+    target: Vec2,
+    /// Time left showing the whole level before following the player
+    overview: Timer,
+    /// Level the overview was last shown for, reloads of it don't show it again
+    level: Option<LevelSelection>,
+}
+
+/// Shows the whole level for a moment whenever a new one is entered. The
+/// game is paused until the camera starts following the player.
+fn start_overview(
+    mut time: ResMut<Time>,
+    level_bounds: Res<LevelBounds>,
+    level_selection: Res<LevelSelection>,
+    mut camera_query: Query<
+        (
+            &mut CameraController,
+            &mut Transform,
+            &mut OrthographicProjection,
+        ),
+        With<MainCamera>,
+    >,
+) {
+    if !level_bounds.is_changed() {
+        return;
+    }
+
+    let Ok((mut controller, mut transform, mut projection)) = camera_query.get_single_mut() else { return; };
+
+    if controller.level.as_ref() == Some(&*level_selection) {
+        return;
+    }
+
+    let center = level_bounds.0.center();
+
+    controller.level = Some(level_selection.clone());
+    controller.target = center;
+    controller.overview = Timer::from_seconds(CAMERA_OVERVIEW_DURATION, TimerMode::Once);
+    transform.translation.x = center.x;
+    transform.translation.y = center.y;
+    projection.scale = overview_scale(level_bounds.0);
+    time.pause();
+}
+
+/// Eases the camera towards a point ahead of the player, which only moves
+/// once the player leaves the dead zone around it. The view never leaves
+/// the level. Follows the interpolated player, the simulated one moves in
+/// steps that don't line up with frames.
+fn follow_player(
+    mut time: ResMut<Time>,
+    fixed_time: Res<FixedTime>,
+    level_bounds: Res<LevelBounds>,
+    player_query: Query<(&Transform, &PreviousTranslation, &Velocity), With<Player>>,
+    mut camera_query: Query<
+        (
+            &mut CameraController,
+            &mut Transform,
+            &mut OrthographicProjection,
+        ),
+        (With<MainCamera>, Without<Player>),
+    >,
+) {
+    let Ok((mut controller, mut transform, mut projection)) = camera_query.get_single_mut() else { return; };
+    let Ok((player_transform, previous, velocity)) = player_query.get_single() else { return; };
+
+    // Real time, the game is paused during the overview
+    controller.overview.tick(time.raw_delta());
+
+    if !controller.overview.finished() {
+        return;
+    }
+
+    if controller.overview.just_finished() {
+        time.unpause();
+    }
+
+    let smoothing = 1. - (-CAMERA_SMOOTHING * time.delta_seconds()).exp();
+    projection.scale += (1. - projection.scale) * smoothing;
+
+    let look_ahead = (velocity.0.truncate() * CAMERA_LOOK_AHEAD)
+        .clamp_length_max(CAMERA_DEAD_ZONE.max_element());
+    let player_position =
+        interpolated_translation(&fixed_time, previous, player_transform).truncate();
+    let focus = player_position + look_ahead;
+    let offset = focus - controller.target;
+    controller.target += offset - offset.clamp(-CAMERA_DEAD_ZONE, CAMERA_DEAD_ZONE);
+
+    let position = transform.translation.truncate();
+    let position = clamp_to_level(
+        position + (controller.target - position) * smoothing,
+        level_bounds.0,
+        projection.scale,
+    );
+
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+}
+
+/// Zoomed out as far as it takes to fit the level in the window
+fn overview_scale(level: Rect) -> f32 {
+    let size = level.size();
+    (size.x / WINDOW_WIDTH).max(size.y / WINDOW_HEIGHT).max(1.)
+}
+
+/// Keeps the view inside the level, centred on it along axes where the
+/// level is smaller than the view
+fn clamp_to_level(position: Vec2, level: Rect, scale: f32) -> Vec2 {
+    let half_view = Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT) * scale / 2.;
+    let min = level.min + half_view;
+    let max = level.max - half_view;
+
+    Vec2::new(
+        if min.x < max.x {
+            position.x.clamp(min.x, max.x)
+        } else {
+            level.center().x
+        },
+        if min.y < max.y {
+            position.y.clamp(min.y, max.y)
+        } else {
+            level.center().y
+        },
+    )
+}
@@ -1,24 +1,167 @@
+use std::ops::{BitOr, Not};
+
 use bevy::prelude::*;
+use serde::Deserialize;
 
 use crate::config::UNIT;
 
-#[derive(Component)]
+/// Shape of a collider within its `size`. Circles use the shorter side as
+/// diameter, capsules run along the longer side and turn with the entity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum ColliderShape {
+    #[default]
+    Aabb,
+    Circle,
+    Capsule,
+}
+
+/// Bit set of collision layers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layers(pub u32);
+
+impl Layers {
+    pub const PLAYER: Self = Self(1 << 0);
+    pub const ENEMY: Self = Self(1 << 1);
+    pub const PROJECTILE: Self = Self(1 << 2);
+    pub const WALL: Self = Self(1 << 3);
+    pub const GLASS: Self = Self(1 << 4);
+    /// Line of sight rays
+    pub const SIGHT: Self = Self(1 << 5);
+    pub const ALL: Self = Self(u32::MAX);
+
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for Layers {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl Not for Layers {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+/// The layers a collider is on and the layers it collides with. Two
+/// colliders only interact if each one is on a layer the other collides
+/// with, by default everything collides with everything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub memberships: Layers,
+    pub filters: Layers,
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self::new(Layers::ALL, Layers::ALL)
+    }
+}
+
+impl CollisionLayers {
+    pub fn new(memberships: Layers, filters: Layers) -> Self {
+        Self {
+            memberships,
+            filters,
+        }
+    }
+
+    pub fn interacts(&self, other: &CollisionLayers) -> bool {
+        self.memberships.intersects(other.filters) && other.memberships.intersects(self.filters)
+    }
+}
+
+#[derive(Component, Clone, Copy)]
 pub struct StaticCollider {
     pub size: Vec2,
+    pub shape: ColliderShape,
+    /// Sensors only report overlaps through collision events, nothing is
+    /// ever blocked by them
+    pub sensor: bool,
+    /// Blocks only bodies coming from the side this direction points to,
+    /// bodies coming from anywhere else pass through
+    pub one_way: Option<Vec2>,
+    pub layers: CollisionLayers,
+}
+
+impl Default for StaticCollider {
+    fn default() -> Self {
+        Self {
+            size: Vec2::splat(UNIT),
+            shape: ColliderShape::Aabb,
+            sensor: false,
+            one_way: None,
+            layers: CollisionLayers::default(),
+        }
+    }
+}
+
+impl StaticCollider {
+    /// Whether a contact with `normal`, pointing away from this collider,
+    /// should stop the body
+    pub fn blocks(&self, normal: Vec2) -> bool {
+        !self.sensor && self.one_way.map_or(true, |side| normal.dot(side) > 0.)
+    }
 }
 
 #[derive(Component)]
 pub struct DynamicCollider {
     pub size: Vec2,
+    pub shape: ColliderShape,
+    pub layers: CollisionLayers,
 }
 
 impl Default for DynamicCollider {
     fn default() -> Self {
         Self {
             size: Vec2::splat(UNIT),
+            shape: ColliderShape::Aabb,
+            layers: CollisionLayers::default(),
         }
     }
 }
 
+/// Makes a dynamic collider push other solid dynamic colliders out of the
+/// way instead of passing through them. Heavier bodies get pushed less, a
+/// body without mass can't be moved by others.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Solid {
+    pub mass: f32,
+}
+
+impl Default for Solid {
+    fn default() -> Self {
+        Self { mass: 1. }
+    }
+}
+
+impl Solid {
+    pub fn inverse_mass(&self) -> f32 {
+        if self.mass > 0. {
+            1. / self.mass
+        } else {
+            0.
+        }
+    }
+}
+
+/// Where an entity entered the level
+#[derive(Component, Clone, Copy, Default)]
+pub struct SpawnPoint(pub Vec2);
+
+/// Units per second, integrated by the physics plugin every simulation step
 #[derive(Component, Default)]
 pub struct Velocity(pub Vec3);
+
+/// Translation at the start of the last simulation step. Rendering
+/// interpolates between this and the current translation, so movement stays
+/// smooth when frames and simulation steps don't line up.
+#[derive(Component, Default)]
+pub struct PreviousTranslation(pub Vec3);
@@ -1,14 +1,49 @@
-use bevy::prelude::Color;
+use bevy::prelude::{Color, Vec2};
 
 pub const WINDOW_WIDTH: f32 = 1200.;
 pub const WINDOW_HEIGHT: f32 = 800.;
 
+// Simulation
+pub const TIMESTEP: f32 = 1. / 60.;
+
 // Player
 pub const UNIT: f32 = 16.;
 pub const ACCELERATION: f32 = 2.;
 pub const DECELERATION: f32 = 10.;
 pub const SPEED: f32 = 600.;
-pub const HISTORY_LENGTH: usize = 50;
+// Simulation steps of player history, enough for ten seconds of echo delay
+pub const HISTORY_LENGTH: usize = (10. / TIMESTEP) as usize;
+
+// Enemies
+pub const MAX_RANDOM_ECHO_DELAY: f32 = 0.8;
+
+// Movers
+pub const MOVER_SPEED: f32 = 60.;
+pub const MOVER_PAUSE: f32 = 0.5;
+
+// Projectiles
+pub const PROJECTILE_LIFETIME: f32 = 5.;
+pub const PROJECTILE_RANGE: f32 = 2000.;
+
+// Death effects
+pub const DEATH_PARTICLES: usize = 16;
+pub const DEATH_PARTICLE_SPEED: f32 = 150.;
+pub const DEATH_PARTICLE_MAX_AGE: f32 = 0.5;
+// Real time seconds the game is slowed down for when an enemy dies
+pub const HIT_STOP_DURATION: f32 = 0.08;
+pub const HIT_STOP_SPEED: f32 = 0.1;
+// Leave a stain where enemies died, until the level is reloaded
+pub const DEATH_DECALS: bool = true;
+
+// Camera
+// Seconds the whole level is shown when it starts, zero to skip
+pub const CAMERA_OVERVIEW_DURATION: f32 = 1.5;
+// How quickly the camera catches up, higher is snappier
+pub const CAMERA_SMOOTHING: f32 = 5.;
+// Half size of the area the player can move in without moving the camera
+pub const CAMERA_DEAD_ZONE: Vec2 = Vec2::new(80., 60.);
+// Seconds of movement the camera looks ahead
+pub const CAMERA_LOOK_AHEAD: f32 = 0.3;
 
 // Footsteps
 pub const FOOTSTEP_INTERVAL: f32 = 0.02;
@@ -0,0 +1,115 @@
+use crate::config::*;
+use crate::enemy::EnemyDied;
+use crate::GameState;
+use bevy::prelude::*;
+use rand::Rng;
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+pub struct EffectsPlugin;
+
+/// Particles, hit-stop and decals when enemies die
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HitStop>()
+            .add_systems((death_effects, update_particles).in_set(OnUpdate(GameState::Playing)))
+            .add_system(update_hit_stop);
+    }
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    age: f32,
+    max_age: f32,
+}
+
+/// Stain left where an enemy died
+#[derive(Component)]
+pub struct Decal;
+
+/// Slows the game down for a moment, measured in real time so it isn't
+/// slowed down itself
+#[derive(Resource, Default)]
+struct HitStop(Timer);
+
+fn death_effects(
+    mut commands: Commands,
+    mut events: EventReader<EnemyDied>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time: ResMut<Time>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for event in events.iter() {
+        for _ in 0..DEATH_PARTICLES {
+            let direction = Vec2::from_angle(rng.gen_range(0.0..TAU));
+            let speed = DEATH_PARTICLE_SPEED * rng.gen_range(0.3..1.);
+
+            commands.spawn((
+                Particle {
+                    velocity: direction * speed,
+                    age: 0.,
+                    max_age: DEATH_PARTICLE_MAX_AGE * rng.gen_range(0.5..1.),
+                },
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: event.color,
+                        custom_size: Some(Vec2::splat(3.)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(event.position.extend(15.)),
+                    ..Default::default()
+                },
+            ));
+        }
+
+        if DEATH_DECALS {
+            let mut color = event.color;
+            color.set_a(0.25);
+
+            commands.spawn((
+                Decal,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(UNIT)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(event.position.extend(2.))
+                        .with_rotation(Quat::from_rotation_z(rng.gen_range(0.0..FRAC_PI_2))),
+                    ..Default::default()
+                },
+            ));
+        }
+
+        hit_stop.0 = Timer::from_seconds(HIT_STOP_DURATION, TimerMode::Once);
+        time.set_relative_speed(HIT_STOP_SPEED);
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in query.iter_mut() {
+        particle.age += time.delta_seconds();
+
+        if particle.age >= particle.max_age {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.);
+        sprite.color.set_a(1. - particle.age / particle.max_age);
+    }
+}
+
+fn update_hit_stop(mut hit_stop: ResMut<HitStop>, mut time: ResMut<Time>) {
+    let raw_delta = time.raw_delta();
+    hit_stop.0.tick(raw_delta);
+
+    if hit_stop.0.just_finished() {
+        time.set_relative_speed(1.);
+    }
+}
@@ -1,81 +1,310 @@
-use std::time::Duration;
-
+use crate::archetype::EnemyArchetype;
 use crate::components::*;
 use crate::config::*;
-use crate::environment::Wall;
+use crate::environment::LevelBounds;
 use crate::loading::AudioAssets;
+use crate::physics::ColliderKind;
+use crate::physics::CollisionStarted;
+use crate::physics::Crushed;
+use crate::physics::PhysicsQuery;
+use crate::physics::PhysicsSet;
 use crate::player::Player;
-use crate::player::PlayerVelocityHistory;
+use crate::player::PlayerHistory;
 use crate::GameState;
 use bevy::prelude::*;
-use bevy::sprite::collide_aabb::collide;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::sprite::MaterialMesh2dBundle;
 use bevy_kira_audio::Audio;
 use bevy_kira_audio::AudioControl;
 use rand::Rng;
+use serde::Deserialize;
 
 pub struct EnemyPlugin;
 
 #[derive(Component, Default)]
 pub struct Enemy {
-    shoot_timer: Timer,
-    offset: usize,
+    alert: AlertState,
+    alert_timer: Timer,
+    reaction_time: f32,
+    aim_time: f32,
+    cooldown: f32,
+    projectile_speed: f32,
+    projectile_lifetime: f32,
+    projectile_range: f32,
+    fire_pattern: FirePattern,
+    projectile_kind: ProjectileKind,
+    /// Shots fired so far in the current burst
+    shots_fired: u32,
+    vision_range: f32,
+    /// Full field of view in radians
+    vision_cone: Option<f32>,
+    echo: Echo,
     can_see_player: bool,
 }
 
+/// What an enemy is doing about the player. Shots are telegraphed: enemies
+/// need a moment to react after spotting the player, then show where they
+/// aim before they fire.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlertState {
+    #[default]
+    Idle,
+    Spotted,
+    Aiming,
+    Firing,
+    Cooldown,
+}
+
+impl Enemy {
+    fn enter(&mut self, alert: AlertState, seconds: f32) {
+        self.alert = alert;
+        self.alert_timer = Timer::from_seconds(seconds, TimerMode::Once);
+    }
+}
+
+/// How an enemy follows the player's history
+#[derive(Clone, Copy, Debug)]
+pub struct Echo {
+    pub mode: EchoMode,
+    /// Seconds the enemy trails behind the player, at most the length of
+    /// the history
+    pub delay: f32,
+    pub variant: EchoVariant,
+    /// Scales the echoed movement, 2 covers twice the distance in the same
+    /// time
+    pub speed: f32,
+}
+
+impl Default for Echo {
+    fn default() -> Self {
+        Self {
+            mode: EchoMode::default(),
+            delay: 0.,
+            variant: EchoVariant::default(),
+            speed: 1.,
+        }
+    }
+}
+
+/// How an enemy echoes the player's history
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum EchoMode {
+    /// Replays the player's velocity, drifts off the path once it bumps
+    /// into something
+    #[default]
+    Velocity,
+    /// Walks to where the player was
+    Footprints,
+    /// Walks the player's path, moved over to start at its own spawn
+    Relative,
+}
+
+/// How an enemy transforms the movement it echoes, relative to its spawn
+/// point. Each variant has its own colour so players can tell them apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum EchoVariant {
+    #[default]
+    Copy,
+    /// Swaps left and right
+    MirrorX,
+    /// Swaps up and down
+    MirrorY,
+    /// Moves the opposite way
+    Inverted,
+    /// Turned a quarter counter-clockwise
+    Rotated,
+}
+
+impl EchoVariant {
+    pub fn apply(&self, vector: Vec2) -> Vec2 {
+        match self {
+            EchoVariant::Copy => vector,
+            EchoVariant::MirrorX => Vec2::new(-vector.x, vector.y),
+            EchoVariant::MirrorY => Vec2::new(vector.x, -vector.y),
+            EchoVariant::Inverted => -vector,
+            EchoVariant::Rotated => vector.perp(),
+        }
+    }
+
+    pub fn apply_rotation(&self, rotation: Quat) -> Quat {
+        if *self == EchoVariant::Copy {
+            return rotation;
+        }
+
+        let direction = self.apply((rotation * Vec3::Y).truncate());
+        Quat::from_rotation_arc(Vec3::Y, direction.extend(0.))
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            EchoVariant::Copy => COLOR6,
+            EchoVariant::MirrorX => COLOR3,
+            EchoVariant::MirrorY => COLOR1,
+            EchoVariant::Inverted => COLOR8,
+            EchoVariant::Rotated => COLOR2,
+        }
+    }
+}
+
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            (
-                update_enemy_velocity,
-                rotate_enemy,
-                projectile_hit,
-                shoot,
-                enemy_squash,
-                can_see_player,
-                update_projectiles,
-            )
-                .in_set(OnUpdate(GameState::Playing)),
-        );
+        app.add_event::<ProjectileExpired>()
+            .add_event::<EnemyDied>()
+            .add_systems((spawn_vision_cones, spawn_aim_lines))
+            .add_systems(
+                (
+                    update_enemy_velocity.before(PhysicsSet::Movement),
+                    steer_projectiles.before(PhysicsSet::Movement),
+                    rotate_enemy,
+                    projectile_hit.after(PhysicsSet::Broadphase),
+                    update_alert.after(can_see_player),
+                    shoot.after(update_alert),
+                    update_aim_lines.after(update_alert),
+                    kill_enemies.after(PhysicsSet::Broadphase),
+                    can_see_player.after(PhysicsSet::Broadphase),
+                    update_projectiles.after(PhysicsSet::Movement),
+                )
+                    .distributive_run_if(in_state(GameState::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
     }
 }
 
 #[derive(Component)]
 pub struct Projectile {
-    direction: Vec3,
-    speed: f32,
+    pub kind: ProjectileKind,
+    /// Whoever fired it, never hit by it
+    pub owner: Option<Entity>,
+    lifetime: Timer,
+    /// Units left to fly
+    range: f32,
+}
+
+impl Projectile {
+    pub fn new(kind: ProjectileKind, owner: Option<Entity>, lifetime: f32, range: f32) -> Self {
+        Self {
+            kind,
+            owner,
+            lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+            range,
+        }
+    }
+}
+
+/// Sent when a projectile is despawned without hitting anything
+pub struct ProjectileExpired {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub cause: ExpiryCause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpiryCause {
+    Lifetime,
+    Range,
+    /// Left the current level
+    OutOfBounds,
+}
+
+/// How an enemy fires a volley
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum FirePattern {
+    #[default]
+    Single,
+    /// `count` shots at once, fanned out over `angle` degrees
+    Spread { count: u32, angle: f32 },
+    /// `count` shots one after another, `interval` seconds apart
+    Burst { count: u32, interval: f32 },
 }
 
+/// How a projectile flies
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum ProjectileKind {
+    #[default]
+    Straight,
+    /// Turns towards the player by up to `turn_rate` degrees per second
+    Homing { turn_rate: f32 },
+    /// Bounces off walls `bounces` times before it is destroyed
+    Ricochet { bounces: u32 },
+}
+
+/// Sent when an enemy is killed, after it has been despawned
+pub struct EnemyDied {
+    pub position: Vec2,
+    pub color: Color,
+    pub cause: DeathCause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathCause {
+    /// Ran into another enemy
+    Squash,
+    /// Shot by another enemy
+    Projectile,
+    /// Crushed by a moving wall
+    Hazard,
+}
+
+/// Translucent fan showing what an enemy with a field of view can see
+#[derive(Component)]
+pub struct VisionCone;
+
+/// Line from an aiming enemy to the player, warning of the coming shot
+#[derive(Component)]
+pub struct AimLine;
+
 #[derive(Bundle)]
 pub struct EnemyBundle {
     enemy: Enemy,
+    spawn_point: SpawnPoint,
     velocity: Velocity,
+    previous_translation: PreviousTranslation,
     collider: DynamicCollider,
     sprite_bundle: SpriteBundle,
 }
 
 impl EnemyBundle {
-    pub fn new(position: Vec3) -> Self {
-        let mut rng = rand::thread_rng();
-        let mut shoot_timer = Timer::new(
-            Duration::from_secs_f32(rng.gen_range(1.8..2.2)),
-            TimerMode::Repeating,
-        );
-        shoot_timer.set_elapsed(Duration::from_secs_f32(rng.gen_range(0.0..1.0)));
+    pub fn new(
+        position: Vec3,
+        archetype: &EnemyArchetype,
+        shape: ColliderShape,
+        echo: Echo,
+    ) -> Self {
+        let size = Vec2::new(archetype.size.0, archetype.size.1);
 
         Self {
             enemy: Enemy {
-                shoot_timer,
-                offset: rng.gen_range(0..50),
+                alert: AlertState::Idle,
+                alert_timer: Timer::default(),
+                reaction_time: archetype.reaction_time,
+                aim_time: archetype.aim_time,
+                cooldown: archetype.cooldown,
+                projectile_speed: archetype.projectile_speed,
+                projectile_lifetime: archetype.projectile_lifetime,
+                projectile_range: archetype.projectile_range,
+                fire_pattern: archetype.fire_pattern,
+                projectile_kind: archetype.projectile,
+                shots_fired: 0,
+                vision_range: archetype.vision_range,
+                vision_cone: archetype
+                    .vision_cone
+                    .filter(|cone| *cone < 360.)
+                    .map(f32::to_radians),
+                echo,
                 can_see_player: false,
             },
+            spawn_point: SpawnPoint(position.truncate()),
             velocity: Velocity::default(),
+            previous_translation: PreviousTranslation(position),
             collider: DynamicCollider {
-                size: Vec2::splat(UNIT),
+                size,
+                shape,
+                layers: CollisionLayers::new(Layers::ENEMY, Layers::ALL),
             },
             sprite_bundle: SpriteBundle {
                 sprite: Sprite {
-                    color: COLOR6,
-                    custom_size: Some(Vec2::new(UNIT, UNIT / 2.)),
+                    color: archetype.color.unwrap_or(echo.variant.color()),
+                    custom_size: Some(size),
                     ..default()
                 },
                 transform: Transform::from_translation(position),
@@ -86,16 +315,56 @@ impl EnemyBundle {
 }
 
 fn update_enemy_velocity(
-    mut enemy_velocity_query: Query<(&mut Velocity, &Enemy)>,
-    mut player_velocity_history: ResMut<PlayerVelocityHistory>,
+    fixed_time: Res<FixedTime>,
+    player_history: Res<PlayerHistory>,
+    player_query: Query<&SpawnPoint, With<Player>>,
+    mut enemy_query: Query<(&mut Velocity, &mut Transform, &SpawnPoint, &Enemy)>,
 ) {
-    for (mut enemy_velocity, enemy) in enemy_velocity_query.iter_mut() {
-        enemy_velocity.0 = player_velocity_history.get(enemy.offset);
+    let delta = fixed_time.period.as_secs_f32();
+    let player_spawn_point = player_query.get_single().ok();
+
+    for (mut velocity, mut transform, spawn_point, enemy) in enemy_query.iter_mut() {
+        let echo = enemy.echo;
+        let steps = ((echo.delay / delta).round() as usize).min(HISTORY_LENGTH - 1);
+
+        let Some(entry) = player_history.get(steps) else {
+            velocity.0 = Vec3::ZERO;
+            continue;
+        };
+
+        let target = match echo.mode {
+            EchoMode::Velocity => {
+                velocity.0 =
+                    (echo.variant.apply(entry.velocity.truncate()) * echo.speed).extend(0.);
+                continue;
+            }
+            EchoMode::Footprints => {
+                spawn_point.0 + echo.variant.apply(entry.position - spawn_point.0)
+            }
+            EchoMode::Relative => {
+                let Some(player_spawn_point) = player_spawn_point else { continue; };
+                let travelled = entry.position - player_spawn_point.0;
+                spawn_point.0 + echo.variant.apply(travelled) * echo.speed
+            }
+        };
+
+        // Head for the recorded position, but never faster than the player
+        // can run, so enemies knocked off the path walk back onto it
+        let difference = target - transform.translation.truncate();
+        velocity.0 = (difference / delta)
+            .clamp_length_max(SPEED * echo.speed)
+            .extend(0.);
+        transform.rotation = echo.variant.apply_rotation(entry.heading);
     }
 }
 
-fn rotate_enemy(mut enemy_query: Query<(&mut Transform, &Velocity), With<Enemy>>) {
-    for (mut transform, velocity) in enemy_query.iter_mut() {
+fn rotate_enemy(mut enemy_query: Query<(&mut Transform, &Velocity, &Enemy)>) {
+    for (mut transform, velocity, enemy) in enemy_query.iter_mut() {
+        // Position based modes copy the player's heading instead
+        if enemy.echo.mode != EchoMode::Velocity {
+            continue;
+        }
+
         if velocity.0.length() > 0. {
             transform.rotation = Quat::from_rotation_arc(Vec3::Y, velocity.0.normalize());
         }
@@ -104,166 +373,437 @@ fn rotate_enemy(mut enemy_query: Query<(&mut Transform, &Velocity), With<Enemy>>
 
 fn update_projectiles(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Transform, &Projectile)>,
-    time: Res<Time>,
+    mut query: Query<(Entity, &mut Projectile, &Transform, &PreviousTranslation)>,
+    fixed_time: Res<FixedTime>,
+    level_bounds: Res<LevelBounds>,
+    mut events: EventWriter<ProjectileExpired>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
 ) {
-    for (entity, mut transform, projectile) in query.iter_mut() {
-        transform.translation += projectile.direction * projectile.speed * time.delta_seconds();
+    for (entity, mut projectile, transform, previous_translation) in query.iter_mut() {
+        let position = transform.translation.truncate();
 
-        if transform.translation.x > WINDOW_WIDTH
-            || transform.translation.x < 0.
-            || transform.translation.y > WINDOW_HEIGHT
-            || transform.translation.y < 0.
-        {
+        projectile.lifetime.tick(fixed_time.period);
+        projectile.range -= position.distance(previous_translation.0.truncate());
+
+        let cause = if !level_bounds.0.contains(position) {
             audio.play(audio_assets.hit_wall.clone()).with_volume(0.1);
-            commands.entity(entity).despawn();
-        }
+            ExpiryCause::OutOfBounds
+        } else if projectile.lifetime.finished() {
+            ExpiryCause::Lifetime
+        } else if projectile.range <= 0. {
+            ExpiryCause::Range
+        } else {
+            continue;
+        };
+
+        commands.entity(entity).despawn();
+        events.send(ProjectileExpired {
+            entity,
+            position,
+            cause,
+        });
     }
 }
 
 fn can_see_player(
-    player_query: Query<(&Transform, &DynamicCollider), With<Player>>,
+    physics: PhysicsQuery,
+    player_query: Query<(Entity, &Transform), With<Player>>,
     mut enemy_query: Query<(&Transform, &mut Enemy)>,
-    static_collider_query: Query<(&StaticCollider, &Transform), With<Wall>>,
 ) {
-    let Ok((player_transform, player_collider)) = player_query.get_single() else { return; };
-    let max_distance: f32 = 1000.;
-    let check_size = Vec2::splat(UNIT);
+    let Ok((player, player_transform)) = player_query.get_single() else { return; };
 
     for (enemy_transform, mut enemy) in enemy_query.iter_mut() {
-        let direction = player_transform.translation - enemy_transform.translation;
-        let local_max_distance = max_distance.min(direction.length());
-        let step = direction.normalize() * UNIT;
-        let mut traveled = Vec3::ZERO;
-        enemy.can_see_player = false;
-
-        'outer: loop {
-            while traveled.length() < local_max_distance {
-                for (collider, collider_transform) in static_collider_query.iter() {
-                    if collide(
-                        traveled + enemy_transform.translation,
-                        check_size,
-                        collider_transform.translation,
-                        collider.size,
-                    )
-                    .is_some()
-                    {
-                        enemy.can_see_player = false;
-                        break 'outer;
-                    }
-                }
-
-                if collide(
-                    traveled + enemy_transform.translation,
-                    check_size,
-                    player_transform.translation,
-                    player_collider.size,
-                )
-                .is_some()
-                {
-                    enemy.can_see_player = true;
-                    break 'outer;
-                }
+        let origin = enemy_transform.translation.truncate();
+        let direction = player_transform.translation.truncate() - origin;
+
+        if let Some(cone) = enemy.vision_cone {
+            let facing = (enemy_transform.rotation * Vec3::Y).truncate();
+
+            if facing.angle_between(direction).abs() > cone / 2. {
+                enemy.can_see_player = false;
+                continue;
+            }
+        }
+
+        // Other enemies don't block the view, only walls do
+        let hit = physics.raycast(
+            origin,
+            direction,
+            enemy.vision_range,
+            CollisionLayers::new(Layers::SIGHT, Layers::PLAYER | Layers::WALL | Layers::GLASS),
+            |_| true,
+        );
+
+        enemy.can_see_player = hit.map_or(false, |hit| hit.entity == player);
+    }
+}
+
+fn spawn_vision_cones(
+    mut commands: Commands,
+    enemy_query: Query<(Entity, &Enemy, &Sprite), Added<Enemy>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, enemy, sprite) in enemy_query.iter() {
+        let Some(cone) = enemy.vision_cone else { continue; };
+        let mut color = sprite.color;
+        color.set_a(0.08);
+
+        let vision_cone = commands
+            .spawn((
+                VisionCone,
+                MaterialMesh2dBundle {
+                    mesh: meshes.add(fan_mesh(enemy.vision_range, cone)).into(),
+                    material: materials.add(ColorMaterial::from(color)),
+                    // Just below the enemy
+                    transform: Transform::from_xyz(0., 0., -1.),
+                    ..default()
+                },
+            ))
+            .id();
+
+        commands.entity(entity).add_child(vision_cone);
+    }
+}
+
+/// Triangle fan around the local Y axis, which is the way enemies face
+fn fan_mesh(radius: f32, angle: f32) -> Mesh {
+    // Roughly one segment every 5 degrees keeps the arc round
+    let segments = ((angle.to_degrees() / 5.).ceil() as u32).max(1);
+
+    let mut positions = vec![[0., 0., 0.]];
+
+    for i in 0..=segments {
+        let theta = -angle / 2. + angle * i as f32 / segments as f32;
+        let (sin, cos) = theta.sin_cos();
+        positions.push([-sin * radius, cos * radius, 0.]);
+    }
 
-                traveled += step;
+    let indices = (1..=segments).flat_map(|i| [0, i, i + 1]).collect();
+    let normals = vec![[0., 0., 1.]; positions.len()];
+    let uvs = vec![[0., 0.]; positions.len()];
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+fn update_alert(
+    mut enemy_query: Query<&mut Enemy>,
+    fixed_time: Res<FixedTime>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for mut enemy in enemy_query.iter_mut() {
+        enemy.alert_timer.tick(fixed_time.period);
+        let finished = enemy.alert_timer.finished();
+
+        match enemy.alert {
+            AlertState::Idle if enemy.can_see_player => {
+                // Vary the reaction a little, so enemies spotting the player
+                // together don't fire in unison
+                let reaction_time = enemy.reaction_time * rng.gen_range(0.8..1.2);
+                enemy.enter(AlertState::Spotted, reaction_time);
+            }
+            AlertState::Spotted | AlertState::Aiming if !enemy.can_see_player => {
+                enemy.enter(AlertState::Idle, 0.);
+            }
+            AlertState::Spotted if finished => {
+                audio.play(audio_assets.aim_tell.clone()).with_volume(0.2);
+                let aim_time = enemy.aim_time;
+                enemy.enter(AlertState::Aiming, aim_time);
             }
+            AlertState::Aiming if finished => enemy.enter(AlertState::Firing, 0.),
+            AlertState::Cooldown if finished => enemy.enter(AlertState::Idle, 0.),
+            _ => {}
         }
     }
 }
 
 fn shoot(
     mut commands: Commands,
-    mut enemy_query: Query<(&Transform, &mut Enemy)>,
-    time: Res<Time>,
+    mut enemy_query: Query<(Entity, &Transform, &mut Enemy)>,
     player_query: Query<&Transform, (With<Player>, Without<Enemy>)>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
 ) {
-    if let Ok(player_transform) = player_query.get_single() {
-        for (transform, mut enemy) in enemy_query.iter_mut() {
-            enemy.shoot_timer.tick(time.delta());
-
-            if enemy.shoot_timer.just_finished() && enemy.can_see_player {
-                audio
-                    .play(audio_assets.laser_shoot.clone())
-                    .with_volume(0.1);
-
-                commands.spawn((
-                    Projectile {
-                        direction: (player_transform.translation - transform.translation)
-                            .normalize(),
-                        speed: 300.,
-                    },
-                    SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::YELLOW,
-                            custom_size: Some(Vec2::splat(3.)),
-                            ..default()
-                        },
-                        transform: Transform::from_translation(Vec3 {
-                            x: transform.translation.x,
-                            y: transform.translation.y,
-                            z: transform.translation.z - 1.,
-                        }),
-                        ..Default::default()
+    let Ok(player_transform) = player_query.get_single() else { return; };
+
+    for (entity, transform, mut enemy) in enemy_query.iter_mut() {
+        // Bursts stay in `Firing` between their shots, the first one goes
+        // off right away
+        if enemy.alert != AlertState::Firing
+            || (enemy.shots_fired > 0 && !enemy.alert_timer.finished())
+        {
+            continue;
+        }
+
+        enemy.shots_fired += 1;
+
+        match enemy.fire_pattern {
+            FirePattern::Burst { count, interval } if enemy.shots_fired < count => {
+                enemy.enter(AlertState::Firing, interval);
+            }
+            _ => {
+                let cooldown = enemy.cooldown;
+                enemy.shots_fired = 0;
+                enemy.enter(AlertState::Cooldown, cooldown);
+            }
+        }
+
+        audio
+            .play(audio_assets.laser_shoot.clone())
+            .with_volume(0.1);
+
+        let direction = (player_transform.translation - transform.translation)
+            .truncate()
+            .normalize_or_zero();
+        let position = Vec3 {
+            x: transform.translation.x,
+            y: transform.translation.y,
+            z: transform.translation.z - 1.,
+        };
+
+        let directions = match enemy.fire_pattern {
+            FirePattern::Spread { count, angle } if count > 1 => {
+                // Fan the shots out evenly, centred on the player
+                let angle = angle.to_radians();
+
+                (0..count)
+                    .map(|i| {
+                        let offset = -angle / 2. + angle * i as f32 / (count - 1) as f32;
+                        Vec2::from_angle(offset).rotate(direction)
+                    })
+                    .collect()
+            }
+            _ => vec![direction],
+        };
+
+        for direction in directions {
+            commands.spawn((
+                Projectile::new(
+                    enemy.projectile_kind,
+                    Some(entity),
+                    enemy.projectile_lifetime,
+                    enemy.projectile_range,
+                ),
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::YELLOW,
+                        custom_size: Some(Vec2::splat(3.)),
+                        ..default()
                     },
-                    DynamicCollider {
-                        size: Vec2::splat(2.),
+                    transform: Transform::from_translation(position),
+                    ..Default::default()
+                },
+                DynamicCollider {
+                    size: Vec2::splat(2.),
+                    shape: ColliderShape::Circle,
+                    layers: CollisionLayers::new(Layers::PROJECTILE, Layers::ALL),
+                },
+                Velocity((direction * enemy.projectile_speed).extend(0.)),
+                PreviousTranslation(position),
+            ));
+        }
+    }
+}
+
+/// Turns homing projectiles towards the player, no faster than their turn
+/// rate
+fn steer_projectiles(
+    fixed_time: Res<FixedTime>,
+    player_query: Query<&Transform, With<Player>>,
+    mut projectile_query: Query<(&Projectile, &Transform, &mut Velocity), Without<Player>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return; };
+    let delta = fixed_time.period.as_secs_f32();
+
+    for (projectile, transform, mut velocity) in projectile_query.iter_mut() {
+        let ProjectileKind::Homing { turn_rate } = projectile.kind else { continue; };
+
+        let current = velocity.0.truncate();
+        let wanted = (player_transform.translation - transform.translation).truncate();
+        let max_turn = turn_rate.to_radians() * delta;
+        let turn = current.angle_between(wanted);
+
+        if turn.is_nan() {
+            continue;
+        }
+
+        let turn = turn.clamp(-max_turn, max_turn);
+        velocity.0 = Vec2::from_angle(turn).rotate(current).extend(0.);
+    }
+}
+
+fn spawn_aim_lines(mut commands: Commands, enemy_query: Query<Entity, Added<Enemy>>) {
+    for entity in enemy_query.iter() {
+        let aim_line = commands
+            .spawn((
+                AimLine,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(1., 1., 0., 0.4),
+                        ..default()
                     },
-                ));
-            }
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+            ))
+            .id();
+
+        commands.entity(entity).add_child(aim_line);
+    }
+}
+
+/// Stretches the aim line of aiming enemies from the enemy to the player
+fn update_aim_lines(
+    enemy_query: Query<(&Enemy, &Transform, &Children)>,
+    player_query: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    mut aim_line_query: Query<
+        (&mut Transform, &mut Sprite, &mut Visibility),
+        (With<AimLine>, Without<Enemy>, Without<Player>),
+    >,
+) {
+    let player_transform = player_query.get_single().ok();
+
+    for (enemy, transform, children) in enemy_query.iter() {
+        for child in children.iter() {
+            let Ok((mut line_transform, mut sprite, mut visibility)) =
+                aim_line_query.get_mut(*child)
+            else {
+                continue;
+            };
+
+            let Some(player_transform) =
+                player_transform.filter(|_| enemy.alert == AlertState::Aiming)
+            else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+
+            // The line is a child, so work in the enemy's rotated space
+            let offset = transform.rotation.inverse()
+                * (player_transform.translation - transform.translation);
+            let offset = offset.truncate();
+
+            *visibility = Visibility::Inherited;
+            sprite.custom_size = Some(Vec2::new(1., offset.length()));
+            line_transform.translation = (offset / 2.).extend(-0.5);
+            line_transform.rotation =
+                Quat::from_rotation_arc(Vec3::Y, offset.normalize_or_zero().extend(0.));
         }
     }
 }
 
 fn projectile_hit(
     mut commands: Commands,
-    walls: Query<(&StaticCollider, &Transform), With<Wall>>,
-    projectiles: Query<(Entity, &DynamicCollider, &Transform), With<Projectile>>,
+    mut collisions: EventReader<CollisionStarted>,
+    mut projectiles: Query<(&mut Projectile, &mut Velocity)>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
 ) {
-    for (w_collider, w_transform) in walls.iter() {
-        for (entity, p_collider, p_transform) in projectiles.iter() {
-            let collision = collide(
-                w_transform.translation,
-                w_collider.size,
-                p_transform.translation,
-                p_collider.size,
-            );
+    let mut despawned = Vec::new();
+
+    for collision in collisions.iter() {
+        // Whatever a projectile can't pass through stops it, glass and the
+        // like are filtered out by the collision layers
+        if collision.kinds.1 != ColliderKind::Static || despawned.contains(&collision.a) {
+            continue;
+        }
 
-            if collision.is_some() {
-                audio.play(audio_assets.hit_wall.clone()).with_volume(0.1);
-                commands.entity(entity).despawn();
+        let Ok((mut projectile, mut velocity)) = projectiles.get_mut(collision.a) else { continue; };
+
+        audio.play(audio_assets.hit_wall.clone()).with_volume(0.1);
+
+        if let ProjectileKind::Ricochet { bounces } = &mut projectile.kind {
+            // Reflect off the wall, unless it was hit on the way out
+            let normal = collision.normal.extend(0.);
+            let into_wall = velocity.0.dot(normal);
+
+            if *bounces > 0 && into_wall < 0. {
+                *bounces -= 1;
+                velocity.0 -= 2. * into_wall * normal;
+                continue;
             }
         }
+
+        commands.entity(collision.a).despawn();
+        despawned.push(collision.a);
     }
 }
 
-fn enemy_squash(
+/// Kills enemies squashed into each other, shot by another enemy or crushed
+/// by a moving wall. Projectiles never hit the enemy that fired them, so
+/// echoes can be led into each other's line of fire.
+fn kill_enemies(
     mut commands: Commands,
-    query: Query<(Entity, &Transform, &DynamicCollider), With<Enemy>>,
-    audio: Res<Audio>,
-    audio_assets: Res<AudioAssets>,
+    mut collisions: EventReader<CollisionStarted>,
+    mut crushed: EventReader<Crushed>,
+    projectile_query: Query<&Projectile>,
+    enemy_query: Query<(&Transform, &Sprite), With<Enemy>>,
+    mut events: EventWriter<EnemyDied>,
 ) {
-    let combinations = query.iter_combinations();
-
-    for [(entity, transform, collider), (other_entity, other_transform, other_collider)] in
-        combinations
-    {
-        if collide(
-            transform.translation,
-            collider.size,
-            other_transform.translation,
-            other_collider.size,
-        )
-        .is_some()
+    let mut killed = Vec::new();
+    let mut spent = Vec::new();
+
+    for collision in collisions.iter() {
+        if enemy_query.contains(collision.a) && enemy_query.contains(collision.b) {
+            killed.extend([
+                (collision.a, DeathCause::Squash),
+                (collision.b, DeathCause::Squash),
+            ]);
+            continue;
+        }
+
+        let (projectile, enemy) = if projectile_query.contains(collision.a) {
+            (collision.a, collision.b)
+        } else {
+            (collision.b, collision.a)
+        };
+
+        let Ok(Projectile { owner, .. }) = projectile_query.get(projectile) else { continue; };
+
+        if *owner == Some(enemy)
+            || !enemy_query.contains(enemy)
+            || spent.contains(&projectile)
+            || killed.iter().any(|(entity, _)| *entity == enemy)
         {
-            audio.play(audio_assets.explosion.clone()).with_volume(0.2);
-            commands.entity(entity).despawn();
-            commands.entity(other_entity).despawn();
+            continue;
+        }
+
+        commands.entity(projectile).despawn();
+        spent.push(projectile);
+        killed.push((enemy, DeathCause::Projectile));
+    }
+
+    killed.extend(
+        crushed
+            .iter()
+            .map(|crushed| (crushed.entity, DeathCause::Hazard)),
+    );
+
+    let mut despawned = Vec::new();
+
+    for (entity, cause) in killed {
+        let Ok((transform, sprite)) = enemy_query.get(entity) else { continue; };
+
+        if despawned.contains(&entity) {
+            continue;
         }
+
+        commands.entity(entity).despawn_recursive();
+        despawned.push(entity);
+
+        events.send(EnemyDied {
+            position: transform.translation.truncate(),
+            color: sprite.color,
+            cause,
+        });
     }
 }
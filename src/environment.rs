@@ -1,15 +1,28 @@
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
+use bevy::utils::{HashMap, HashSet};
+use bevy_ecs_ldtk::ldtk::FieldValue;
 use bevy_ecs_ldtk::prelude::*;
 
+use crate::archetype::EnemyArchetype;
+use crate::archetype::EnemyRegistry;
 use crate::components::*;
 use crate::config::*;
+use crate::effects::Decal;
+use crate::enemy::Echo;
+use crate::enemy::EchoMode;
+use crate::enemy::EchoVariant;
 use crate::enemy::EnemyBundle;
+use crate::enemy::FirePattern;
 use crate::enemy::Projectile;
+use crate::enemy::ProjectileKind;
 use crate::loading::LdtkLevelAssets;
+use crate::mechanisms::{Door, KeyBundle, PressurePlateBundle};
+use crate::physics::{Mover, MoverMode};
 use crate::player::Footstep;
 use crate::player::PlayerBundle;
 use crate::GameState;
+use rand::Rng;
 
 pub struct EnvironmentPlugin;
 
@@ -21,7 +34,11 @@ impl Plugin for EnvironmentPlugin {
                 level_background: LevelBackground::Nonexistent,
                 ..default()
             })
+            .init_resource::<LevelBounds>()
+            .register_ldtk_int_cell_for_layer::<WallCellBundle>(WALL_LAYER, 1)
             .add_system(spawn_ldtk_entities)
+            .add_system(spawn_wall_colliders)
+            .add_system(update_level_bounds)
             .add_system(setup_level.in_schedule(OnEnter(GameState::Playing)));
     }
 }
@@ -31,6 +48,64 @@ pub struct Wall;
 #[derive(Component)]
 pub struct Goal;
 
+/// IntGrid layer holding the plain walls of a level. Walls made of anything
+/// else, or with a shape, are still placed as `WallSpawner` entities.
+const WALL_LAYER: &str = "Walls";
+
+/// One cell of the wall layer, merged with its neighbours into colliders by
+/// `spawn_wall_colliders`
+#[derive(Component, Default)]
+struct WallCell;
+
+#[derive(Bundle, LdtkIntCell, Default)]
+struct WallCellBundle {
+    wall_cell: WallCell,
+}
+
+/// World space area covered by the current LDtk level
+#[derive(Resource)]
+pub struct LevelBounds(pub Rect);
+
+impl Default for LevelBounds {
+    fn default() -> Self {
+        Self(Rect::new(0., 0., WINDOW_WIDTH, WINDOW_HEIGHT))
+    }
+}
+
+/// What a wall is made of, which decides what passes through it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WallMaterial {
+    #[default]
+    Wall,
+    /// Blocks movement, but not sight or projectiles
+    Glass,
+    /// Only blocks enemies
+    EnemyBarrier,
+}
+
+impl WallMaterial {
+    pub fn layers(&self) -> CollisionLayers {
+        match self {
+            WallMaterial::Wall => CollisionLayers::new(Layers::WALL, Layers::ALL),
+            WallMaterial::Glass => {
+                CollisionLayers::new(Layers::GLASS, !(Layers::SIGHT | Layers::PROJECTILE))
+            }
+            WallMaterial::EnemyBarrier => CollisionLayers::new(Layers::WALL, Layers::ENEMY),
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        let (mut color, alpha) = match self {
+            WallMaterial::Wall => (COLOR7, 1.),
+            WallMaterial::Glass => (COLOR1, 0.4),
+            WallMaterial::EnemyBarrier => (COLOR6, 0.3),
+        };
+
+        color.set_a(alpha);
+        color
+    }
+}
+
 #[derive(Bundle)]
 pub struct WallBundle {
     wall: Wall,
@@ -39,13 +114,25 @@ pub struct WallBundle {
 }
 
 impl WallBundle {
-    pub fn new(position: Vec3, size: Vec2) -> Self {
+    pub fn new(position: Vec3, collider: StaticCollider, material: WallMaterial) -> Self {
+        let mut color = material.color();
+
+        // Trigger volumes are only hinted at
+        if collider.sensor {
+            color.set_a(0.2);
+        }
+
+        let size = collider.size;
+
         Self {
             wall: Wall,
-            collider: StaticCollider { size },
+            collider: StaticCollider {
+                layers: material.layers(),
+                ..collider
+            },
             sprite_bundle: SpriteBundle {
                 sprite: Sprite {
-                    color: COLOR7,
+                    color,
                     custom_size: Some(size),
                     ..default()
                 },
@@ -69,30 +156,158 @@ fn setup_level(
         ldtk_handle: level_assets.level.clone(),
         ..default()
     });
+}
+
+/// Takes the level bounds from the pixel size of the level once it is in
+/// place, and walls it in. The walls go with the level when it is despawned.
+fn update_level_bounds(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    level_query: Query<(Entity, &Handle<LdtkLevel>, &Transform)>,
+    levels: Res<Assets<LdtkLevel>>,
+    mut level_bounds: ResMut<LevelBounds>,
+) {
+    for level_event in level_events.iter() {
+        let LevelEvent::Transformed(iid) = level_event else { continue; };
+
+        for (level, handle, transform) in level_query.iter() {
+            let Some(ldtk_level) = levels.get(handle) else { continue; };
 
-    // Bottom
-    commands.spawn(WallBundle::new(
-        Vec3::new(WINDOW_WIDTH / 2. + UNIT, UNIT / 2., 1.),
-        Vec2::new(WINDOW_WIDTH, UNIT),
-    ));
+            if ldtk_level.level.iid != *iid {
+                continue;
+            }
 
-    // Top
-    commands.spawn(WallBundle::new(
-        Vec3::new(WINDOW_WIDTH / 2. + UNIT, WINDOW_HEIGHT + UNIT * 1.5, 1.),
-        Vec2::new(WINDOW_WIDTH, UNIT),
-    ));
+            let min = transform.translation.truncate();
+            let size = Vec2::new(
+                ldtk_level.level.px_wid as f32,
+                ldtk_level.level.px_hei as f32,
+            );
+            level_bounds.0 = Rect::from_corners(min, min + size);
 
-    // Left
-    commands.spawn(WallBundle::new(
-        Vec3::new(UNIT / 2., WINDOW_HEIGHT / 2. + UNIT, 1.),
-        Vec2::new(UNIT, WINDOW_HEIGHT),
-    ));
+            // Just outside the level, relative to it
+            let borders = [
+                (
+                    Vec2::new(size.x / 2., -UNIT / 2.),
+                    Vec2::new(size.x + UNIT * 2., UNIT),
+                ),
+                (
+                    Vec2::new(size.x / 2., size.y + UNIT / 2.),
+                    Vec2::new(size.x + UNIT * 2., UNIT),
+                ),
+                (Vec2::new(-UNIT / 2., size.y / 2.), Vec2::new(UNIT, size.y)),
+                (
+                    Vec2::new(size.x + UNIT / 2., size.y / 2.),
+                    Vec2::new(UNIT, size.y),
+                ),
+            ];
 
-    // Right
-    commands.spawn(WallBundle::new(
-        Vec3::new(WINDOW_WIDTH + UNIT * 1.5, WINDOW_HEIGHT / 2. + UNIT, 1.),
-        Vec2::new(UNIT, WINDOW_HEIGHT),
-    ));
+            commands.entity(level).with_children(|level| {
+                for (center, size) in borders {
+                    level.spawn(WallBundle::new(
+                        center.extend(1.),
+                        StaticCollider { size, ..default() },
+                        WallMaterial::Wall,
+                    ));
+                }
+            });
+        }
+    }
+}
+
+/// Covers the wall cells of each newly spawned level with as few
+/// rectangular colliders as it can. The cells themselves are drawn by the
+/// layer's tileset.
+fn spawn_wall_colliders(
+    mut commands: Commands,
+    wall_query: Query<(&GridCoords, &Parent), Added<WallCell>>,
+    layer_query: Query<&Parent, Without<WallCell>>,
+    level_query: Query<&Handle<LdtkLevel>>,
+    levels: Res<Assets<LdtkLevel>>,
+) {
+    // Cells are children of their layer, which is a child of the level
+    let mut level_cells: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+
+    for (grid_coords, parent) in wall_query.iter() {
+        let Ok(level) = layer_query.get(parent.get()) else { continue; };
+
+        level_cells
+            .entry(level.get())
+            .or_default()
+            .insert(*grid_coords);
+    }
+
+    for (level, cells) in level_cells {
+        let Ok(handle) = level_query.get(level) else { continue; };
+        let Some(ldtk_level) = levels.get(handle) else { continue; };
+        let Some(layer) = ldtk_level
+            .level
+            .layer_instances
+            .iter()
+            .flatten()
+            .find(|layer| layer.identifier == WALL_LAYER)
+        else {
+            continue;
+        };
+
+        let grid_size = layer.grid_size as f32;
+
+        commands.entity(level).with_children(|level| {
+            for (min, size) in merge_cells(&cells, layer.c_wid, layer.c_hei) {
+                let size = size.as_vec2() * grid_size;
+                let center = min.as_vec2() * grid_size + size / 2.;
+
+                level.spawn((
+                    Wall,
+                    StaticCollider {
+                        size,
+                        layers: WallMaterial::Wall.layers(),
+                        ..default()
+                    },
+                    TransformBundle::from_transform(Transform::from_translation(center.extend(5.))),
+                ));
+            }
+        });
+    }
+}
+
+/// Greedily merges grid cells into rectangles, returned as their lowest
+/// cell and size in cells. Each rectangle grows as wide as it can along a
+/// row, then as tall as the rows above it allow.
+fn merge_cells(cells: &HashSet<GridCoords>, width: i32, height: i32) -> Vec<(IVec2, IVec2)> {
+    let mut merged = HashSet::new();
+    let mut rectangles = Vec::new();
+    let free = |merged: &HashSet<GridCoords>, x: i32, y: i32| {
+        let coords = GridCoords::new(x, y);
+        cells.contains(&coords) && !merged.contains(&coords)
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            if !free(&merged, x, y) {
+                continue;
+            }
+
+            let mut size = IVec2::ONE;
+
+            while x + size.x < width && free(&merged, x + size.x, y) {
+                size.x += 1;
+            }
+
+            while y + size.y < height && (x..x + size.x).all(|x| free(&merged, x, y + size.y)) {
+                size.y += 1;
+            }
+
+            for cell_y in y..y + size.y {
+                for cell_x in x..x + size.x {
+                    merged.insert(GridCoords::new(cell_x, cell_y));
+                }
+            }
+
+            rectangles.push((IVec2::new(x, y), size));
+        }
+    }
+
+    rectangles
 }
 
 fn spawn_ldtk_entities(
@@ -100,9 +315,14 @@ fn spawn_ldtk_entities(
     entity_query: Query<(Entity, &Transform, &EntityInstance), Added<EntityInstance>>,
     projectiles: Query<Entity, With<Projectile>>,
     footsteps: Query<Entity, With<Footstep>>,
+    decals: Query<Entity, With<Decal>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    enemy_registry: Res<EnemyRegistry>,
+    enemy_archetypes: Res<Assets<EnemyArchetype>>,
 ) {
+    let default_archetype = EnemyArchetype::default();
+
     for (entity, transform, entity_instance) in entity_query.iter() {
         for entity in &projectiles {
             commands.entity(entity).despawn();
@@ -112,18 +332,69 @@ fn spawn_ldtk_entities(
             commands.entity(entity).despawn();
         }
 
+        for entity in &decals {
+            commands.entity(entity).despawn();
+        }
+
         let mut position = transform.translation.clone();
+        let collider_shape = shape_field(entity_instance);
 
         if entity_instance.identifier == *"PlayerSpawner" {
             position.z = 30.;
-            commands.entity(entity).insert(PlayerBundle::new(position));
+            commands.entity(entity).insert(PlayerBundle::new(
+                position,
+                collider_shape.unwrap_or(ColliderShape::Capsule),
+            ));
+
+            if let Some(solid) = solid_field(entity_instance) {
+                commands.entity(entity).insert(solid);
+            }
         } else if entity_instance.identifier == *"EnemySpawner" {
             position.z = 20.;
-            commands.entity(entity).insert(EnemyBundle::new(position));
+
+            let name = string_field(entity_instance, "Archetype").unwrap_or("default");
+            let mut archetype = enemy_registry
+                .get(name, &enemy_archetypes)
+                .unwrap_or_else(|| {
+                    warn!("Unknown enemy archetype {name}");
+                    &default_archetype
+                })
+                .clone();
+
+            // A field of view of 360 degrees or more lets the enemy see all
+            // around, even if its archetype has a vision cone
+            if let Some(field_of_view) = float_field(entity_instance, "FieldOfView") {
+                archetype.vision_cone = Some(field_of_view);
+            }
+
+            if let Some(fire_pattern) = fire_pattern_field(entity_instance) {
+                archetype.fire_pattern = fire_pattern;
+            }
+
+            if let Some(projectile) = projectile_kind_field(entity_instance) {
+                archetype.projectile = projectile;
+            }
+
+            commands.entity(entity).insert(EnemyBundle::new(
+                position,
+                &archetype,
+                collider_shape.unwrap_or(archetype.shape),
+                echo_field(entity_instance, &archetype),
+            ));
+
+            if let Some(solid) = solid_field(entity_instance) {
+                commands.entity(entity).insert(solid);
+            }
         } else if entity_instance.identifier == *"Goal" {
             position.z = 10.;
             commands.entity(entity).insert((
                 Goal,
+                StaticCollider {
+                    size: Vec2::splat(UNIT * 1.4),
+                    shape: ColliderShape::Circle,
+                    sensor: true,
+                    ..default()
+                },
                 MaterialMesh2dBundle {
                     mesh: meshes.add(shape::Circle::new(UNIT * 0.7).into()).into(),
                     material: materials.add(ColorMaterial::from(Color::GREEN)),
@@ -134,9 +405,381 @@ fn spawn_ldtk_entities(
         } else if entity_instance.identifier == *"WallSpawner" {
             position.z = 5.;
             let size = Vec2::new(transform.scale.x, transform.scale.y) * UNIT;
-            commands
-                .entity(entity)
-                .insert(WallBundle::new(position, size));
+            commands.entity(entity).insert(WallBundle::new(
+                position,
+                StaticCollider {
+                    size,
+                    shape: collider_shape.unwrap_or_default(),
+                    sensor: bool_field(entity_instance, "Sensor"),
+                    one_way: one_way_field(entity_instance),
+                    ..default()
+                },
+                material_field(entity_instance).unwrap_or_default(),
+            ));
+
+            if let Some(mover) = mover_field(entity_instance, position.truncate()) {
+                commands
+                    .entity(entity)
+                    .insert((mover, PreviousTranslation(position)));
+            }
+        } else if entity_instance.identifier == *"Door" {
+            position.z = 5.;
+            let size = Vec2::new(transform.scale.x, transform.scale.y) * UNIT;
+            commands.entity(entity).insert((
+                Door::new(
+                    entity_instance.iid.clone(),
+                    bool_field(entity_instance, "Open"),
+                ),
+                WallBundle::new(
+                    position,
+                    StaticCollider { size, ..default() },
+                    WallMaterial::Wall,
+                ),
+            ));
+        } else if entity_instance.identifier == *"PressurePlate" {
+            position.z = 4.;
+            commands.entity(entity).insert(PressurePlateBundle::new(
+                position,
+                entity_refs_field(entity_instance, "Targets"),
+            ));
+        } else if entity_instance.identifier == *"Key" {
+            position.z = 10.;
+            commands.entity(entity).insert(KeyBundle::new(
+                position,
+                entity_refs_field(entity_instance, "Targets"),
+            ));
+        }
+    }
+}
+
+fn field<'a>(entity_instance: &'a EntityInstance, identifier: &str) -> Option<&'a FieldValue> {
+    entity_instance
+        .field_instances
+        .iter()
+        .find(|field| field.identifier == identifier)
+        .map(|field| &field.value)
+}
+
+/// Reads the optional "Shape" enum field, entities without it keep the
+/// default shape of their bundle
+fn shape_field(entity_instance: &EntityInstance) -> Option<ColliderShape> {
+    let Some(FieldValue::Enum(Some(shape))) = field(entity_instance, "Shape") else { return None; };
+
+    match shape.as_str() {
+        "Aabb" => Some(ColliderShape::Aabb),
+        "Circle" => Some(ColliderShape::Circle),
+        "Capsule" => Some(ColliderShape::Capsule),
+        _ => {
+            warn!(
+                "Unknown collider shape {shape} on {}",
+                entity_instance.identifier
+            );
+            None
+        }
+    }
+}
+
+/// Reads the optional "Solid" bool and "Mass" float fields
+fn solid_field(entity_instance: &EntityInstance) -> Option<Solid> {
+    if !bool_field(entity_instance, "Solid") {
+        return None;
+    }
+
+    match float_field(entity_instance, "Mass") {
+        Some(mass) => Some(Solid { mass }),
+        None => Some(Solid::default()),
+    }
+}
+
+fn bool_field(entity_instance: &EntityInstance, identifier: &str) -> bool {
+    matches!(
+        field(entity_instance, identifier),
+        Some(FieldValue::Bool(true))
+    )
+}
+
+/// Reads the optional "OneWay" enum field, naming the side the wall is solid
+/// from
+fn one_way_field(entity_instance: &EntityInstance) -> Option<Vec2> {
+    let Some(FieldValue::Enum(Some(side))) = field(entity_instance, "OneWay") else { return None; };
+
+    match side.as_str() {
+        "Up" => Some(Vec2::Y),
+        "Down" => Some(Vec2::NEG_Y),
+        "Left" => Some(Vec2::NEG_X),
+        "Right" => Some(Vec2::X),
+        _ => {
+            warn!(
+                "Unknown one-way side {side} on {}",
+                entity_instance.identifier
+            );
+            None
+        }
+    }
+}
+
+/// Reads the optional "Material" enum field of walls
+fn material_field(entity_instance: &EntityInstance) -> Option<WallMaterial> {
+    let Some(FieldValue::Enum(Some(material))) = field(entity_instance, "Material") else { return None; };
+
+    match material.as_str() {
+        "Wall" => Some(WallMaterial::Wall),
+        "Glass" => Some(WallMaterial::Glass),
+        "EnemyBarrier" => Some(WallMaterial::EnemyBarrier),
+        _ => {
+            warn!(
+                "Unknown wall material {material} on {}",
+                entity_instance.identifier
+            );
+            None
+        }
+    }
+}
+
+/// Reads the optional "EchoMode" enum field of enemies
+fn echo_mode_field(entity_instance: &EntityInstance) -> Option<EchoMode> {
+    let Some(FieldValue::Enum(Some(mode))) = field(entity_instance, "EchoMode") else { return None; };
+
+    match mode.as_str() {
+        "Velocity" => Some(EchoMode::Velocity),
+        "Footprints" => Some(EchoMode::Footprints),
+        "Relative" => Some(EchoMode::Relative),
+        _ => {
+            warn!("Unknown echo mode {mode} on {}", entity_instance.identifier);
+            None
         }
     }
 }
+
+/// Reads the optional "EchoMode", "Delay", "Variant" and "Speed" fields of
+/// enemies, falling back to their archetype. Without a delay enemies trail
+/// the player by a random moment, so they don't all walk on top of each
+/// other.
+fn echo_field(entity_instance: &EntityInstance, archetype: &EnemyArchetype) -> Echo {
+    let delay = float_field(entity_instance, "Delay")
+        .or(archetype.echo_delay)
+        .unwrap_or_else(|| rand::thread_rng().gen_range(0.0..MAX_RANDOM_ECHO_DELAY));
+
+    Echo {
+        mode: echo_mode_field(entity_instance).unwrap_or(archetype.echo_mode),
+        delay: delay.max(0.),
+        variant: variant_field(entity_instance).unwrap_or(archetype.echo_variant),
+        speed: float_field(entity_instance, "Speed").unwrap_or(archetype.echo_speed),
+    }
+}
+
+/// Reads the optional "Waypoints" point array of walls, with its "Loop",
+/// "Speed" and "Pause" fields. The wall starts out at its own position, the
+/// first of its waypoints.
+fn mover_field(entity_instance: &EntityInstance, position: Vec2) -> Option<Mover> {
+    let Some(FieldValue::Points(points)) = field(entity_instance, "Waypoints") else { return None; };
+
+    // Points are in LDtk grid cells, which count downwards
+    let waypoints: Vec<Vec2> = std::iter::once(position)
+        .chain(points.iter().flatten().map(|point| {
+            let offset = *point - entity_instance.grid;
+            position + Vec2::new(offset.x as f32, -offset.y as f32) * UNIT
+        }))
+        .collect();
+
+    if waypoints.len() < 2 {
+        return None;
+    }
+
+    let mode = if bool_field(entity_instance, "Loop") {
+        MoverMode::Loop
+    } else {
+        MoverMode::PingPong
+    };
+
+    Some(Mover::new(
+        waypoints,
+        mode,
+        float_field(entity_instance, "Speed").unwrap_or(MOVER_SPEED),
+        float_field(entity_instance, "Pause").unwrap_or(MOVER_PAUSE),
+    ))
+}
+
+/// Iids of the entities referenced by an entity reference field, or an array
+/// of them
+fn entity_refs_field(entity_instance: &EntityInstance, identifier: &str) -> Vec<String> {
+    match field(entity_instance, identifier) {
+        Some(FieldValue::EntityRef(Some(reference))) => vec![reference.entity_iid.clone()],
+        Some(FieldValue::EntityRefs(references)) => references
+            .iter()
+            .flatten()
+            .map(|reference| reference.entity_iid.clone())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn string_field<'a>(entity_instance: &'a EntityInstance, identifier: &str) -> Option<&'a str> {
+    match field(entity_instance, identifier) {
+        Some(FieldValue::String(Some(value))) => Some(value.as_str()),
+        _ => None,
+    }
+}
+
+fn float_field(entity_instance: &EntityInstance, identifier: &str) -> Option<f32> {
+    match field(entity_instance, identifier) {
+        Some(FieldValue::Float(Some(value))) => Some(*value),
+        Some(FieldValue::Int(Some(value))) => Some(*value as f32),
+        _ => None,
+    }
+}
+
+/// Reads the optional "FirePattern" enum field of enemies. LDtk enums can't
+/// carry parameters, so each pattern comes with fixed ones, archetypes can
+/// tune them further.
+fn fire_pattern_field(entity_instance: &EntityInstance) -> Option<FirePattern> {
+    let Some(FieldValue::Enum(Some(pattern))) = field(entity_instance, "FirePattern") else { return None; };
+
+    match pattern.as_str() {
+        "Single" => Some(FirePattern::Single),
+        "Spread" => Some(FirePattern::Spread {
+            count: 3,
+            angle: 30.,
+        }),
+        "Burst" => Some(FirePattern::Burst {
+            count: 3,
+            interval: 0.15,
+        }),
+        _ => {
+            warn!(
+                "Unknown fire pattern {pattern} on {}",
+                entity_instance.identifier
+            );
+            None
+        }
+    }
+}
+
+/// Reads the optional "ProjectileKind" enum field of enemies, with fixed
+/// parameters like [`fire_pattern_field`]
+fn projectile_kind_field(entity_instance: &EntityInstance) -> Option<ProjectileKind> {
+    let Some(FieldValue::Enum(Some(kind))) = field(entity_instance, "ProjectileKind") else { return None; };
+
+    match kind.as_str() {
+        "Straight" => Some(ProjectileKind::Straight),
+        "Homing" => Some(ProjectileKind::Homing { turn_rate: 90. }),
+        "Ricochet" => Some(ProjectileKind::Ricochet { bounces: 2 }),
+        _ => {
+            warn!(
+                "Unknown projectile kind {kind} on {}",
+                entity_instance.identifier
+            );
+            None
+        }
+    }
+}
+
+/// Reads the optional "Variant" enum field of enemies
+fn variant_field(entity_instance: &EntityInstance) -> Option<EchoVariant> {
+    let Some(FieldValue::Enum(Some(variant))) = field(entity_instance, "Variant") else { return None; };
+
+    match variant.as_str() {
+        "Copy" => Some(EchoVariant::Copy),
+        "MirrorX" => Some(EchoVariant::MirrorX),
+        "MirrorY" => Some(EchoVariant::MirrorY),
+        "Inverted" => Some(EchoVariant::Inverted),
+        "Rotated" => Some(EchoVariant::Rotated),
+        _ => {
+            warn!(
+                "Unknown echo variant {variant} on {}",
+                entity_instance.identifier
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(coords: &[(i32, i32)]) -> HashSet<GridCoords> {
+        coords.iter().map(|&(x, y)| GridCoords::new(x, y)).collect()
+    }
+
+    /// Every wall cell ends up in exactly one rectangle and no rectangle
+    /// covers an empty cell
+    fn assert_exact_cover(cells: &HashSet<GridCoords>, rectangles: &[(IVec2, IVec2)]) {
+        let mut covered = HashSet::new();
+
+        for &(min, size) in rectangles {
+            for y in min.y..min.y + size.y {
+                for x in min.x..min.x + size.x {
+                    let coords = GridCoords::new(x, y);
+                    assert!(cells.contains(&coords), "{coords:?} is not a wall");
+                    assert!(covered.insert(coords), "{coords:?} is covered twice");
+                }
+            }
+        }
+
+        assert_eq!(covered.len(), cells.len());
+    }
+
+    #[test]
+    fn solid_block_becomes_one_rectangle() {
+        let block = cells(&[(1, 1), (2, 1), (3, 1), (1, 2), (2, 2), (3, 2)]);
+        let rectangles = merge_cells(&block, 5, 5);
+
+        assert_eq!(rectangles, vec![(IVec2::new(1, 1), IVec2::new(3, 2))]);
+    }
+
+    #[test]
+    fn l_shape_becomes_two_rectangles() {
+        let l_shape = cells(&[(0, 0), (1, 0), (2, 0), (0, 1), (0, 2)]);
+        let rectangles = merge_cells(&l_shape, 4, 4);
+
+        assert_eq!(rectangles.len(), 2);
+        assert_exact_cover(&l_shape, &rectangles);
+    }
+
+    #[test]
+    fn thin_walls_stay_separate() {
+        // Two one cell wide columns with a gap between them
+        let columns = cells(&[(0, 0), (0, 1), (0, 2), (2, 0), (2, 1), (2, 2)]);
+        let rectangles = merge_cells(&columns, 3, 3);
+
+        assert_eq!(
+            rectangles,
+            vec![
+                (IVec2::new(0, 0), IVec2::new(1, 3)),
+                (IVec2::new(2, 0), IVec2::new(1, 3))
+            ]
+        );
+    }
+
+    #[test]
+    fn walls_touching_at_a_corner_are_not_merged() {
+        let diagonal = cells(&[(0, 0), (1, 1)]);
+        let rectangles = merge_cells(&diagonal, 2, 2);
+
+        assert_eq!(rectangles.len(), 2);
+        assert_exact_cover(&diagonal, &rectangles);
+    }
+
+    #[test]
+    fn irregular_walls_are_covered_exactly_once() {
+        let walls = cells(&[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (0, 1),
+            (3, 1),
+            (0, 2),
+            (1, 2),
+            (3, 2),
+            (5, 2),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+        ]);
+        let rectangles = merge_cells(&walls, 6, 4);
+
+        assert_exact_cover(&walls, &rectangles);
+    }
+}
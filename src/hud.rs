@@ -2,6 +2,7 @@ use bevy::prelude::*;
 
 use crate::{
     config::{WINDOW_HEIGHT, WINDOW_WIDTH},
+    loading::FontAssets,
     player::PlayerState,
     GameState,
 };
@@ -11,7 +12,8 @@ pub struct HudPlugin;
 impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system(init)
-            .add_system(update.in_set(OnUpdate(GameState::Playing)));
+            .add_system(spawn_score.in_schedule(OnEnter(GameState::Playing)))
+            .add_systems((update, update_score).in_set(OnUpdate(GameState::Playing)));
     }
 }
 
@@ -21,6 +23,9 @@ struct HealthBarFront;
 #[derive(Component)]
 struct HealthBarBack;
 
+#[derive(Component)]
+struct ScoreText;
+
 fn init(mut commands: Commands) {
     commands.spawn((
         HealthBarFront,
@@ -64,3 +69,40 @@ fn update(player_state: Res<PlayerState>, mut bar: Query<&mut Sprite, With<Healt
         }
     }
 }
+
+fn spawn_score(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    player_state: Res<PlayerState>,
+) {
+    commands.spawn((
+        ScoreText,
+        Text2dBundle {
+            text: Text::from_section(
+                player_state.score.to_string(),
+                TextStyle {
+                    font: font_assets.pixeboy.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform::from_translation(Vec3::new(
+                WINDOW_WIDTH - 20.,
+                WINDOW_HEIGHT - 10.,
+                100.,
+            )),
+            ..default()
+        },
+    ));
+}
+
+fn update_score(player_state: Res<PlayerState>, mut text_query: Query<&mut Text, With<ScoreText>>) {
+    if !player_state.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = player_state.score.to_string();
+    }
+}
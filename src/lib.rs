@@ -1,19 +1,28 @@
 mod actions;
+mod archetype;
 mod audio;
+mod camera;
 mod components;
 mod config;
+mod effects;
 mod enemy;
 mod environment;
 mod hud;
 mod loading;
+mod mechanisms;
 mod menu;
 mod physics;
 mod player;
 mod post_processing;
 
 use crate::actions::ActionsPlugin;
+use crate::archetype::ArchetypePlugin;
 use crate::audio::InternalAudioPlugin;
+use crate::camera::CameraPlugin;
+use crate::config::TIMESTEP;
+use crate::effects::EffectsPlugin;
 use crate::loading::LoadingPlugin;
+use crate::mechanisms::MechanismsPlugin;
 use crate::menu::MenuPlugin;
 use crate::player::PlayerPlugin;
 
@@ -28,6 +37,8 @@ use enemy::EnemyPlugin;
 use environment::EnvironmentPlugin;
 use hud::HudPlugin;
 use physics::PhysicsPlugin;
+// Public for the benchmarks
+pub use physics::SpatialGrid;
 use post_processing::PostProcessingPlugin;
 
 // This example game uses States to separate logic
@@ -60,16 +71,23 @@ pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        // Gameplay is simulated in `CoreSchedule::FixedUpdate`, so it behaves
+        // the same regardless of the frame rate
         app.add_state::<GameState>()
+            .insert_resource(FixedTime::new_from_secs(TIMESTEP))
             .add_plugin(LdtkPlugin)
+            .add_plugin(ArchetypePlugin)
             .add_plugin(LoadingPlugin)
             .add_plugin(MenuPlugin)
             .add_plugin(ActionsPlugin)
             .add_plugin(InternalAudioPlugin)
             .add_plugin(EnemyPlugin)
+            .add_plugin(EffectsPlugin)
             .add_plugin(EnvironmentPlugin)
+            .add_plugin(MechanismsPlugin)
             .add_plugin(PlayerPlugin)
             .add_plugin(PostProcessingPlugin)
+            .add_plugin(CameraPlugin)
             .add_plugin(PhysicsPlugin);
 
         #[cfg(debug_assertions)]
@@ -1,3 +1,4 @@
+use crate::archetype::EnemyArchetype;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
@@ -17,7 +18,8 @@ impl Plugin for LoadingPlugin {
         .add_collection_to_loading_state::<_, LdtkLevelAssets>(GameState::Loading)
         .add_collection_to_loading_state::<_, FontAssets>(GameState::Loading)
         .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading);
+        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, EnemyAssets>(GameState::Loading);
     }
 }
 
@@ -53,6 +55,8 @@ pub struct AudioAssets {
     pub hit_hurt: Handle<AudioSource>,
     #[asset(path = "audio/explosion.wav")]
     pub explosion: Handle<AudioSource>,
+    #[asset(path = "audio/aim_tell.wav")]
+    pub aim_tell: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -60,3 +64,20 @@ pub struct TextureAssets {
     #[asset(path = "textures/footstep.png")]
     pub footstep: Handle<Image>,
 }
+
+#[derive(AssetCollection, Resource)]
+pub struct EnemyAssets {
+    // Only held on to so the archetypes stay loaded, they are looked up
+    // through the `EnemyRegistry`. Listed one by one, loading a folder
+    // doesn't work on the web.
+    #[allow(dead_code)]
+    #[asset(
+        paths(
+            "enemies/default.enemy.ron",
+            "enemies/sentry.enemy.ron",
+            "enemies/skitter.enemy.ron"
+        ),
+        collection(typed)
+    )]
+    pub archetypes: Vec<Handle<EnemyArchetype>>,
+}
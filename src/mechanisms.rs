@@ -0,0 +1,241 @@
+use crate::components::*;
+use crate::config::*;
+use crate::enemy::Enemy;
+use crate::environment::WallMaterial;
+use crate::physics::{CollisionEnded, CollisionStarted, PhysicsQuery, PhysicsSet};
+use crate::player::Player;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+pub struct MechanismsPlugin;
+
+/// Doors opened by keys and pressure plates, linked to them by LDtk entity
+/// references
+impl Plugin for MechanismsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (
+                press_plates.after(PhysicsSet::Broadphase),
+                pick_up_keys.after(PhysicsSet::Broadphase),
+                update_doors.after(press_plates).after(pick_up_keys),
+            )
+                .distributive_run_if(in_state(GameState::Playing))
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+    }
+}
+
+/// Toggles the doors it targets while active
+#[derive(Component)]
+pub struct Switch {
+    /// LDtk iids of the targeted doors
+    targets: Vec<String>,
+    active: bool,
+}
+
+/// Active while the player or an enemy stands on it, so echoes replaying
+/// the player's path can hold it down
+#[derive(Component, Default)]
+pub struct PressurePlate {
+    pressed_by: Vec<Entity>,
+}
+
+/// Active for good once the player picks it up
+#[derive(Component)]
+pub struct Key;
+
+#[derive(Component)]
+pub struct Door {
+    /// LDtk iid, which switches refer to it by
+    iid: String,
+    /// Whether the door is open while no switch targets it
+    open_by_default: bool,
+}
+
+impl Door {
+    pub fn new(iid: String, open_by_default: bool) -> Self {
+        Self {
+            iid,
+            open_by_default,
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct PressurePlateBundle {
+    pressure_plate: PressurePlate,
+    switch: Switch,
+    collider: StaticCollider,
+    sprite_bundle: SpriteBundle,
+}
+
+impl PressurePlateBundle {
+    pub fn new(position: Vec3, targets: Vec<String>) -> Self {
+        let size = Vec2::splat(UNIT * 1.5);
+
+        Self {
+            pressure_plate: PressurePlate::default(),
+            switch: Switch {
+                targets,
+                active: false,
+            },
+            collider: StaticCollider {
+                size,
+                sensor: true,
+                layers: CollisionLayers::new(Layers::WALL, Layers::PLAYER | Layers::ENEMY),
+                ..default()
+            },
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: plate_color(false),
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(position),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct KeyBundle {
+    key: Key,
+    switch: Switch,
+    collider: StaticCollider,
+    sprite_bundle: SpriteBundle,
+}
+
+impl KeyBundle {
+    pub fn new(position: Vec3, targets: Vec<String>) -> Self {
+        let size = Vec2::splat(UNIT * 0.75);
+
+        Self {
+            key: Key,
+            switch: Switch {
+                targets,
+                active: false,
+            },
+            collider: StaticCollider {
+                size,
+                shape: ColliderShape::Circle,
+                sensor: true,
+                layers: CollisionLayers::new(Layers::WALL, Layers::PLAYER),
+                ..default()
+            },
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: COLOR5,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(position),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+fn plate_color(pressed: bool) -> Color {
+    let mut color = COLOR4;
+    color.set_a(if pressed { 1. } else { 0.5 });
+    color
+}
+
+fn press_plates(
+    mut started: EventReader<CollisionStarted>,
+    mut ended: EventReader<CollisionEnded>,
+    body_query: Query<(), Or<(With<Player>, With<Enemy>)>>,
+    mut plate_query: Query<(&mut PressurePlate, &mut Switch, &mut Sprite)>,
+) {
+    for collision in started.iter() {
+        let Ok((mut plate, _, _)) = plate_query.get_mut(collision.b) else { continue; };
+
+        if body_query.contains(collision.a) {
+            plate.pressed_by.push(collision.a);
+        }
+    }
+
+    // Also sent for bodies despawned while standing on a plate
+    for collision in ended.iter() {
+        let Ok((mut plate, _, _)) = plate_query.get_mut(collision.b) else { continue; };
+        plate.pressed_by.retain(|body| *body != collision.a);
+    }
+
+    for (plate, mut switch, mut sprite) in plate_query.iter_mut() {
+        let pressed = !plate.pressed_by.is_empty();
+
+        if switch.active != pressed {
+            switch.active = pressed;
+            sprite.color = plate_color(pressed);
+        }
+    }
+}
+
+fn pick_up_keys(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionStarted>,
+    player_query: Query<(), With<Player>>,
+    mut key_query: Query<&mut Switch, With<Key>>,
+) {
+    for collision in collisions.iter() {
+        if !player_query.contains(collision.a) {
+            continue;
+        }
+
+        let Ok(mut switch) = key_query.get_mut(collision.b) else { continue; };
+
+        // The key has to stay around to keep its doors open
+        switch.active = true;
+        commands
+            .entity(collision.b)
+            .remove::<StaticCollider>()
+            .insert(Visibility::Hidden);
+    }
+}
+
+/// Opens or closes doors by turning their collider into a sensor
+fn update_doors(
+    switch_query: Query<&Switch>,
+    mut door_queries: ParamSet<(
+        Query<(Entity, &Door, &mut StaticCollider, &mut Sprite)>,
+        PhysicsQuery,
+    )>,
+) {
+    let targeted: HashSet<&str> = switch_query
+        .iter()
+        .filter(|switch| switch.active)
+        .flat_map(|switch| switch.targets.iter().map(String::as_str))
+        .collect();
+
+    let toggled: Vec<(Entity, bool)> = door_queries
+        .p0()
+        .iter()
+        .filter_map(|(entity, door, collider, _)| {
+            let open = door.open_by_default != targeted.contains(door.iid.as_str());
+            (collider.sensor != open).then_some((entity, open))
+        })
+        .collect();
+
+    // A door closing on a body would trap it inside, so it stays open until
+    // the doorway is clear
+    let physics = door_queries.p1();
+    let toggled: Vec<(Entity, bool)> = toggled
+        .into_iter()
+        .filter(|&(entity, open)| open || !physics.overlaps_dynamic(entity))
+        .collect();
+
+    let mut door_query = door_queries.p0();
+
+    for (entity, open) in toggled {
+        let Ok((_, _, mut collider, mut sprite)) = door_query.get_mut(entity) else { continue; };
+
+        collider.sensor = open;
+        sprite.color = WallMaterial::Wall.color();
+
+        if open {
+            sprite.color.set_a(0.2);
+        }
+    }
+}
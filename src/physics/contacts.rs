@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::narrowphase::{penetration, Shape};
+use super::SpatialGrid;
+use crate::components::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColliderKind {
+    Static,
+    Dynamic,
+    /// A static collider that is only a trigger volume
+    Sensor,
+}
+
+/// Sent on the first simulation step two colliders touch.
+///
+/// `a` is always a dynamic collider. `normal` points from `b` towards `a`,
+/// which is the direction `a` would have to move to get out of `b`.
+#[derive(Clone, Copy, Debug)]
+pub struct CollisionStarted {
+    pub a: Entity,
+    pub b: Entity,
+    pub normal: Vec2,
+    pub kinds: (ColliderKind, ColliderKind),
+}
+
+/// Sent on the first simulation step two colliders stop touching, either
+/// because they moved apart or because one of them was despawned.
+#[derive(Clone, Copy, Debug)]
+pub struct CollisionEnded {
+    pub a: Entity,
+    pub b: Entity,
+    pub kinds: (ColliderKind, ColliderKind),
+}
+
+impl CollisionStarted {
+    /// The other entity if `entity` is part of this collision
+    pub fn other(&self, entity: Entity) -> Option<Entity> {
+        other(self.a, self.b, entity)
+    }
+}
+
+impl CollisionEnded {
+    /// The other entity if `entity` is part of this collision
+    pub fn other(&self, entity: Entity) -> Option<Entity> {
+        other(self.a, self.b, entity)
+    }
+}
+
+fn other(a: Entity, b: Entity, entity: Entity) -> Option<Entity> {
+    if a == entity {
+        Some(b)
+    } else if b == entity {
+        Some(a)
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct Contact {
+    pub normal: Vec2,
+    pub kinds: (ColliderKind, ColliderKind),
+}
+
+/// Pairs touching this step and the step before, keyed by `(a, b)`
+#[derive(Resource, Default)]
+pub(super) struct Contacts {
+    /// Contacts found while sweeping bodies in `move_and_collide`. Bodies
+    /// stopped against a wall no longer overlap it, so these would be missed
+    /// by the overlap tests.
+    pub swept: HashMap<(Entity, Entity), Contact>,
+    touching: HashMap<(Entity, Entity), Contact>,
+}
+
+pub(super) fn detect_collisions(
+    grid: Res<SpatialGrid>,
+    mut contacts: ResMut<Contacts>,
+    static_collider_query: Query<(&Transform, &StaticCollider)>,
+    dynamic_collider_query: Query<(Entity, &Transform, &DynamicCollider)>,
+    mut started: EventWriter<CollisionStarted>,
+    mut ended: EventWriter<CollisionEnded>,
+) {
+    let mut current = std::mem::take(&mut contacts.swept);
+
+    for (entity, transform, collider) in dynamic_collider_query.iter() {
+        let shape = Shape::new(collider.shape, collider.size, transform);
+        let area = shape.bounds();
+
+        for other in grid.query_static(area) {
+            let Ok((other_transform, other_collider)) = static_collider_query.get(other) else { continue; };
+            let other_shape =
+                Shape::new(other_collider.shape, other_collider.size, other_transform);
+
+            if !collider.layers.interacts(&other_collider.layers) {
+                continue;
+            }
+
+            // Bodies stopped by a one-way wall are already in the swept
+            // contacts, overlapping one means passing through from behind
+            if other_collider.one_way.is_some() && !other_collider.sensor {
+                continue;
+            }
+
+            if let Some(hit) = penetration(&shape, &other_shape) {
+                let kind = if other_collider.sensor {
+                    ColliderKind::Sensor
+                } else {
+                    ColliderKind::Static
+                };
+
+                current.entry((entity, other)).or_insert(Contact {
+                    normal: hit.normal,
+                    kinds: (ColliderKind::Dynamic, kind),
+                });
+            }
+        }
+
+        // Every pair is visited from both sides, only handle it once
+        for other in grid
+            .query_dynamic(area)
+            .into_iter()
+            .filter(|other| *other > entity)
+        {
+            let Ok((_, other_transform, other_collider)) = dynamic_collider_query.get(other) else { continue; };
+
+            if !collider.layers.interacts(&other_collider.layers) {
+                continue;
+            }
+
+            let other_shape =
+                Shape::new(other_collider.shape, other_collider.size, other_transform);
+
+            if let Some(hit) = penetration(&shape, &other_shape) {
+                current.insert(
+                    (entity, other),
+                    Contact {
+                        normal: hit.normal,
+                        kinds: (ColliderKind::Dynamic, ColliderKind::Dynamic),
+                    },
+                );
+            }
+        }
+    }
+
+    for (&(a, b), contact) in current.iter() {
+        if !contacts.touching.contains_key(&(a, b)) {
+            started.send(CollisionStarted {
+                a,
+                b,
+                normal: contact.normal,
+                kinds: contact.kinds,
+            });
+        }
+    }
+
+    for (&(a, b), contact) in contacts.touching.iter() {
+        if !current.contains_key(&(a, b)) {
+            ended.send(CollisionEnded {
+                a,
+                b,
+                kinds: contact.kinds,
+            });
+        }
+    }
+
+    contacts.touching = current;
+}
@@ -0,0 +1,298 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::config::UNIT;
+
+/// Uniform grid that buckets collider bounds, so systems only have to test
+/// colliders close to the area they are interested in.
+///
+/// Static colliders are updated incrementally when they change, dynamic
+/// colliders are rebuilt every simulation step.
+#[derive(Resource)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    statics: GridLayer,
+    dynamics: GridLayer,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(UNIT * 4.)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            statics: GridLayer::default(),
+            dynamics: GridLayer::default(),
+        }
+    }
+
+    /// Grid holding only the given static colliders, for the benchmarks
+    #[doc(hidden)]
+    pub fn with_static(
+        cell_size: f32,
+        colliders: impl IntoIterator<Item = (Entity, Rect)>,
+    ) -> Self {
+        let mut grid = Self::new(cell_size);
+
+        for (entity, bounds) in colliders {
+            grid.insert_static(entity, bounds);
+        }
+
+        grid
+    }
+
+    /// Static colliders whose bounds touch `area`
+    pub fn query_static(&self, area: Rect) -> Vec<Entity> {
+        self.statics.query(area, self.cell_size)
+    }
+
+    /// Dynamic colliders whose bounds touch `area`
+    pub fn query_dynamic(&self, area: Rect) -> Vec<Entity> {
+        self.dynamics.query(area, self.cell_size)
+    }
+
+    /// Static colliders in the cells the segment from `start` to `end`
+    /// passes through. Their bounds aren't tested against the segment.
+    pub fn query_static_segment(&self, start: Vec2, end: Vec2) -> Vec<Entity> {
+        self.statics.query_segment(start, end, self.cell_size)
+    }
+
+    /// Dynamic colliders in the cells the segment from `start` to `end`
+    /// passes through. Their bounds aren't tested against the segment.
+    pub fn query_dynamic_segment(&self, start: Vec2, end: Vec2) -> Vec<Entity> {
+        self.dynamics.query_segment(start, end, self.cell_size)
+    }
+
+    pub(super) fn insert_static(&mut self, entity: Entity, bounds: Rect) {
+        self.statics.remove(entity, self.cell_size);
+        self.statics.insert(entity, bounds, self.cell_size);
+    }
+
+    pub(super) fn remove_static(&mut self, entity: Entity) {
+        self.statics.remove(entity, self.cell_size);
+    }
+
+    pub(super) fn insert_dynamic(&mut self, entity: Entity, bounds: Rect) {
+        self.dynamics.remove(entity, self.cell_size);
+        self.dynamics.insert(entity, bounds, self.cell_size);
+    }
+
+    pub(super) fn clear_dynamic(&mut self) {
+        self.dynamics.clear();
+    }
+}
+
+#[derive(Default)]
+struct GridLayer {
+    cells: HashMap<IVec2, Vec<Entity>>,
+    bounds: HashMap<Entity, Rect>,
+}
+
+impl GridLayer {
+    fn insert(&mut self, entity: Entity, bounds: Rect, cell_size: f32) {
+        let (min, max) = cell_range(bounds, cell_size);
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(entity);
+            }
+        }
+
+        self.bounds.insert(entity, bounds);
+    }
+
+    fn remove(&mut self, entity: Entity, cell_size: f32) {
+        let Some(bounds) = self.bounds.remove(&entity) else { return; };
+        let (min, max) = cell_range(bounds, cell_size);
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                if let Some(cell) = self.cells.get_mut(&IVec2::new(x, y)) {
+                    cell.retain(|other| *other != entity);
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        // Keep the allocations around, the dynamic layer is refilled every step
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.bounds.clear();
+    }
+
+    fn query(&self, area: Rect, cell_size: f32) -> Vec<Entity> {
+        let (min, max) = cell_range(area, cell_size);
+        let mut found = Vec::new();
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                let Some(cell) = self.cells.get(&IVec2::new(x, y)) else { continue; };
+
+                found.extend(cell.iter().filter(|entity| {
+                    self.bounds
+                        .get(entity)
+                        .map_or(false, |bounds| overlaps(*bounds, area))
+                }));
+            }
+        }
+
+        // Colliders spanning several cells are found more than once
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    fn query_segment(&self, start: Vec2, end: Vec2, cell_size: f32) -> Vec<Entity> {
+        let mut found = Vec::new();
+
+        for cell in cells_on_segment(start, end, cell_size) {
+            if let Some(cell) = self.cells.get(&cell) {
+                found.extend(cell.iter());
+            }
+        }
+
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+fn cells_on_segment(start: Vec2, end: Vec2, cell_size: f32) -> Vec<IVec2> {
+    let mut cell = (start / cell_size).floor().as_ivec2();
+    let last = (end / cell_size).floor().as_ivec2();
+    let delta = end - start;
+    let step = IVec2::new(
+        (delta.x > 0.) as i32 - (delta.x < 0.) as i32,
+        (delta.y > 0.) as i32 - (delta.y < 0.) as i32,
+    );
+
+    // Fraction of the segment until the next cell boundary on each axis, and
+    // the fraction it takes to cross a whole cell
+    let mut next = Vec2::splat(f32::INFINITY);
+    let mut across = Vec2::splat(f32::INFINITY);
+
+    for axis in 0..2 {
+        if step[axis] == 0 {
+            continue;
+        }
+
+        let boundary = (cell[axis] + step[axis].max(0)) as f32 * cell_size;
+        next[axis] = (boundary - start[axis]) / delta[axis];
+        across[axis] = cell_size / delta[axis].abs();
+    }
+
+    // Walking the grid one cell at a time takes exactly this many steps,
+    // counting them keeps rounding errors from overshooting the last cell
+    let steps = (last - cell).abs();
+    let mut cells = Vec::with_capacity((steps.x + steps.y + 1) as usize);
+    cells.push(cell);
+
+    for _ in 0..steps.x + steps.y {
+        if next.x < next.y {
+            cell.x += step.x;
+            next.x += across.x;
+        } else {
+            cell.y += step.y;
+            next.y += across.y;
+        }
+        cells.push(cell);
+    }
+
+    cells
+}
+
+fn cell_range(bounds: Rect, cell_size: f32) -> (IVec2, IVec2) {
+    (
+        (bounds.min / cell_size).floor().as_ivec2(),
+        (bounds.max / cell_size).floor().as_ivec2(),
+    )
+}
+
+/// Inclusive overlap test, touching edges count so sweeps that end exactly
+/// on a collider still find it
+fn overlaps(a: Rect, b: Rect) -> bool {
+    a.min.x <= b.max.x && a.max.x >= b.min.x && a.min.y <= b.max.y && a.max.y >= b.min.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Consecutive cells share a side, so nothing the segment crosses is
+    /// skipped diagonally
+    fn assert_connected(cells: &[IVec2]) {
+        for pair in cells.windows(2) {
+            let step = (pair[1] - pair[0]).abs();
+            assert_eq!(step.x + step.y, 1, "{:?} to {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn segment_inside_one_cell() {
+        let cells = cells_on_segment(Vec2::new(1., 1.), Vec2::new(9., 9.), 10.);
+
+        assert_eq!(cells, vec![IVec2::ZERO]);
+    }
+
+    #[test]
+    fn horizontal_segment_visits_every_cell() {
+        let cells = cells_on_segment(Vec2::new(1., 1.), Vec2::new(35., 1.), 10.);
+
+        assert_eq!(
+            cells,
+            vec![
+                IVec2::new(0, 0),
+                IVec2::new(1, 0),
+                IVec2::new(2, 0),
+                IVec2::new(3, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn diagonal_segment_ends_in_last_cell() {
+        let cells = cells_on_segment(Vec2::new(1., 1.), Vec2::new(25., 15.), 10.);
+
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells.first(), Some(&IVec2::ZERO));
+        assert_eq!(cells.last(), Some(&IVec2::new(2, 1)));
+        assert_connected(&cells);
+    }
+
+    #[test]
+    fn segment_through_grid_corner() {
+        let cells = cells_on_segment(Vec2::new(5., 5.), Vec2::new(25., 25.), 10.);
+
+        assert_eq!(cells.len(), 5);
+        assert_eq!(cells.last(), Some(&IVec2::new(2, 2)));
+        assert_connected(&cells);
+    }
+
+    #[test]
+    fn backwards_segment_across_origin() {
+        let cells = cells_on_segment(Vec2::new(5., 5.), Vec2::new(-5., -15.), 10.);
+
+        assert_eq!(cells.first(), Some(&IVec2::ZERO));
+        assert_eq!(cells.last(), Some(&IVec2::new(-1, -2)));
+        assert_eq!(cells.len(), 4);
+        assert_connected(&cells);
+    }
+
+    #[test]
+    fn query_finds_touching_colliders_only() {
+        let mut grid = SpatialGrid::new(16.);
+        let wall = Entity::from_raw(0);
+        let far_wall = Entity::from_raw(1);
+        grid.insert_static(wall, Rect::new(0., 0., 40., 4.));
+        grid.insert_static(far_wall, Rect::new(100., 0., 140., 4.));
+
+        assert_eq!(grid.query_static(Rect::new(40., 4., 50., 10.)), vec![wall]);
+        assert!(grid.query_static(Rect::new(50., 0., 90., 4.)).is_empty());
+    }
+}
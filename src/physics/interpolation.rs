@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+
+pub(super) fn store_previous_translation(mut query: Query<(&mut PreviousTranslation, &Transform)>) {
+    for (mut previous, transform) in query.iter_mut() {
+        previous.0 = transform.translation;
+    }
+}
+
+/// Moves the rendered position of interpolated bodies, and their direct
+/// children, to where they would be between the last two simulation steps.
+/// Only the `GlobalTransform` is touched, `Transform` keeps the simulated
+/// position.
+pub(super) fn interpolate_translation(
+    fixed_time: Res<FixedTime>,
+    body_query: Query<(Entity, &PreviousTranslation, &Transform, Option<&Children>)>,
+    mut global_transform_query: Query<&mut GlobalTransform>,
+) {
+    for (entity, previous, transform, children) in body_query.iter() {
+        let offset =
+            interpolated_translation(&fixed_time, previous, transform) - transform.translation;
+
+        if offset == Vec3::ZERO {
+            continue;
+        }
+
+        let children = children
+            .map(|children| children.iter().copied())
+            .into_iter()
+            .flatten();
+
+        for rendered_entity in std::iter::once(entity).chain(children) {
+            if let Ok(mut global_transform) = global_transform_query.get_mut(rendered_entity) {
+                *global_transform = GlobalTransform::from_translation(offset) * *global_transform;
+            }
+        }
+    }
+}
+
+/// Where an interpolated body is rendered this frame. Lets systems that
+/// follow a body in [`CoreSet::Update`] match what is on screen.
+pub fn interpolated_translation(
+    fixed_time: &FixedTime,
+    previous: &PreviousTranslation,
+    transform: &Transform,
+) -> Vec3 {
+    let alpha =
+        (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).clamp(0., 1.);
+
+    previous.0.lerp(transform.translation, alpha)
+}
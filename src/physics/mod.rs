@@ -0,0 +1,263 @@
+use crate::components::*;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+mod contacts;
+mod grid;
+mod interpolation;
+mod mover;
+mod narrowphase;
+mod raycast;
+mod separation;
+
+use contacts::{detect_collisions, Contact, Contacts};
+pub use contacts::{ColliderKind, CollisionEnded, CollisionStarted};
+pub use grid::SpatialGrid;
+pub use interpolation::interpolated_translation;
+use interpolation::{interpolate_translation, store_previous_translation};
+use mover::move_movers;
+pub use mover::{Crushed, Mover, MoverMode};
+use narrowphase::{penetration, sweep_aabb, Shape};
+pub use raycast::{PhysicsQuery, RayHit};
+use separation::separate_solid_bodies;
+
+/// Maximum number of contacts resolved for a single body per sub-step.
+const MAX_SWEEP_ITERATIONS: usize = 4;
+
+/// Gap left between a body and the surface it was stopped against or pushed
+/// out of, so the next test doesn't start inside the collider.
+const SKIN: f32 = 0.01;
+
+pub struct PhysicsPlugin;
+
+/// Physics runs in [`CoreSchedule::FixedUpdate`]. Systems that query the
+/// [`SpatialGrid`] or read collision events should run after
+/// [`PhysicsSet::Broadphase`], when every collider is in its final place for
+/// the step.
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub enum PhysicsSet {
+    Movement,
+    Broadphase,
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CollisionStarted>()
+            .add_event::<CollisionEnded>()
+            .add_event::<Crushed>()
+            .init_resource::<SpatialGrid>()
+            .init_resource::<Contacts>()
+            .edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+                schedule.configure_set(PhysicsSet::Movement.before(PhysicsSet::Broadphase));
+            })
+            // Levels are swapped outside of `GameState::Playing` too, and
+            // removals are only kept for a couple of frames, so keep track of
+            // walls every frame regardless of the state
+            .add_system(update_static_grid)
+            .add_systems(
+                (
+                    store_previous_translation,
+                    move_movers,
+                    move_and_collide,
+                    separate_solid_bodies,
+                )
+                    .chain()
+                    .in_set(PhysicsSet::Movement)
+                    .distributive_run_if(in_state(GameState::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (update_dynamic_grid, detect_collisions)
+                    .chain()
+                    .in_set(PhysicsSet::Broadphase)
+                    .distributive_run_if(in_state(GameState::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                interpolate_translation
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn update_static_grid(
+    mut grid: ResMut<SpatialGrid>,
+    changed_query: Query<
+        (Entity, &Transform, &StaticCollider),
+        Or<(Changed<Transform>, Changed<StaticCollider>)>,
+    >,
+    mut removed: RemovedComponents<StaticCollider>,
+) {
+    for entity in removed.iter() {
+        grid.remove_static(entity);
+    }
+
+    for (entity, transform, collider) in changed_query.iter() {
+        grid.insert_static(
+            entity,
+            Shape::new(collider.shape, collider.size, transform).bounds(),
+        );
+    }
+}
+
+fn update_dynamic_grid(
+    mut grid: ResMut<SpatialGrid>,
+    dynamic_collider_query: Query<(Entity, &Transform, &DynamicCollider)>,
+) {
+    grid.clear_dynamic();
+
+    for (entity, transform, collider) in dynamic_collider_query.iter() {
+        grid.insert_dynamic(
+            entity,
+            Shape::new(collider.shape, collider.size, transform).bounds(),
+        );
+    }
+}
+
+fn move_and_collide(
+    fixed_time: Res<FixedTime>,
+    grid: Res<SpatialGrid>,
+    mut contacts: ResMut<Contacts>,
+    static_collider_query: Query<(&Transform, &StaticCollider)>,
+    mut dynamic_collider_query: Query<
+        (Entity, &mut Transform, &Velocity, &DynamicCollider),
+        Without<StaticCollider>,
+    >,
+) {
+    // Sensors never block, they are left to `detect_collisions`
+    let static_shape = |other: Entity, layers: &CollisionLayers| {
+        let (static_transform, static_collider) = static_collider_query.get(other).ok()?;
+
+        if static_collider.sensor || !layers.interacts(&static_collider.layers) {
+            return None;
+        }
+
+        Some((
+            Shape::new(
+                static_collider.shape,
+                static_collider.size,
+                static_transform,
+            ),
+            static_collider,
+        ))
+    };
+
+    for (entity, mut dynamic_transform, velocity, dynamic_collider) in
+        dynamic_collider_query.iter_mut()
+    {
+        let mut shape = Shape::new(
+            dynamic_collider.shape,
+            dynamic_collider.size,
+            &dynamic_transform,
+        );
+        let start = shape.center();
+        let displacement = velocity.0.truncate() * fixed_time.period.as_secs_f32();
+
+        // Only boxes against boxes can be swept exactly. Everything else
+        // moves in sub-steps short enough not to skip over anything as thick
+        // as the body. Either way the body is pushed back out of whatever it
+        // still overlaps.
+        let step_count = (displacement.length() / shape.min_half_extent().max(1.))
+            .ceil()
+            .max(1.) as usize;
+        let mut step = displacement / step_count as f32;
+
+        for _ in 0..step_count {
+            let before = shape;
+            let mut remaining = step;
+
+            for _ in 0..MAX_SWEEP_ITERATIONS {
+                let Shape::Aabb { center, half_size } = shape else {
+                    shape = shape.translated(remaining);
+                    break;
+                };
+
+                if remaining == Vec2::ZERO {
+                    break;
+                }
+
+                // Everything the body could touch on its way
+                let swept_area = shape.bounds().union(shape.translated(remaining).bounds());
+
+                let closest = grid
+                    .query_static(swept_area)
+                    .into_iter()
+                    .filter_map(|other| {
+                        let (
+                            Shape::Aabb {
+                                center: other_center,
+                                half_size: other_half_size,
+                            },
+                            other_collider,
+                        ) = static_shape(other, &dynamic_collider.layers)?
+                        else {
+                            return None;
+                        };
+
+                        sweep_aabb(center, half_size, remaining, other_center, other_half_size)
+                            .filter(|hit| other_collider.blocks(hit.normal))
+                            .map(|hit| (other, hit))
+                    })
+                    .min_by(|(_, a), (_, b)| a.time.total_cmp(&b.time));
+
+                let Some((other, hit)) = closest else {
+                    shape = shape.translated(remaining);
+                    break;
+                };
+
+                contacts.swept.insert(
+                    (entity, other),
+                    Contact {
+                        normal: hit.normal,
+                        kinds: (ColliderKind::Dynamic, ColliderKind::Static),
+                    },
+                );
+
+                // Move up to the contact, then slide along the surface with
+                // whatever displacement is left
+                shape = shape.translated(remaining * hit.time + hit.normal * SKIN);
+                remaining *= 1. - hit.time;
+                remaining -= hit.normal * remaining.dot(hit.normal);
+                step -= hit.normal * step.dot(hit.normal).min(0.);
+            }
+
+            // Boxes are pushed out too, the sweep ignores walls they already
+            // overlap, like one a mover just pushed them into
+            for other in grid.query_static(shape.bounds()) {
+                let Some((other_shape, other_collider)) =
+                    static_shape(other, &dynamic_collider.layers)
+                else {
+                    continue;
+                };
+
+                let Some(hit) = penetration(&shape, &other_shape) else { continue; };
+
+                // One-way walls only push back bodies that just ran into
+                // them, not ones already on their way through
+                if !other_collider.blocks(hit.normal)
+                    || (other_collider.one_way.is_some()
+                        && penetration(&before, &other_shape).is_some())
+                {
+                    continue;
+                }
+
+                contacts.swept.insert(
+                    (entity, other),
+                    Contact {
+                        normal: hit.normal,
+                        kinds: (ColliderKind::Dynamic, ColliderKind::Static),
+                    },
+                );
+
+                shape = shape.translated(hit.normal * (hit.depth + SKIN));
+                step -= hit.normal * step.dot(hit.normal).min(0.);
+            }
+        }
+
+        let offset = shape.center() - start;
+        dynamic_transform.translation.x += offset.x;
+        dynamic_transform.translation.y += offset.y;
+    }
+}
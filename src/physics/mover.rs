@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+
+use super::contacts::{ColliderKind, Contact, Contacts};
+use super::narrowphase::{penetration, Shape};
+use super::{SpatialGrid, SKIN};
+use crate::components::*;
+
+/// How far a body can be pushed into a wall before it counts as crushed
+const CRUSH_DEPTH: f32 = 2.;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MoverMode {
+    /// Back and forth along the waypoints
+    #[default]
+    PingPong,
+    /// From the last waypoint straight back to the first
+    Loop,
+}
+
+/// Moves a static collider along its waypoints. Solid movers push dynamic
+/// bodies out of their way, sensors carry the bodies on them along.
+#[derive(Component)]
+pub struct Mover {
+    waypoints: Vec<Vec2>,
+    mode: MoverMode,
+    /// Units per second
+    speed: f32,
+    /// Seconds waited at every waypoint
+    pause: f32,
+    next: usize,
+    reversed: bool,
+    waiting: Timer,
+}
+
+impl Mover {
+    pub fn new(waypoints: Vec<Vec2>, mode: MoverMode, speed: f32, pause: f32) -> Self {
+        Self {
+            waypoints,
+            mode,
+            speed,
+            pause,
+            next: 0,
+            reversed: false,
+            waiting: Timer::default(),
+        }
+    }
+
+    fn advance(&mut self) {
+        let last = self.waypoints.len() - 1;
+
+        self.next = match self.mode {
+            MoverMode::Loop => (self.next + 1) % self.waypoints.len(),
+            MoverMode::PingPong => {
+                if self.next == last {
+                    self.reversed = true;
+                } else if self.next == 0 {
+                    self.reversed = false;
+                }
+
+                if self.reversed {
+                    self.next - 1
+                } else {
+                    self.next + 1
+                }
+            }
+        };
+        self.waiting = Timer::from_seconds(self.pause, TimerMode::Once);
+    }
+}
+
+/// Sent when a mover pushes a body into a wall, or into another mover
+#[derive(Clone, Copy, Debug)]
+pub struct Crushed {
+    pub entity: Entity,
+}
+
+struct Move {
+    entity: Entity,
+    before: Shape,
+    after: Shape,
+    collider: StaticCollider,
+}
+
+pub(super) fn move_movers(
+    fixed_time: Res<FixedTime>,
+    mut grid: ResMut<SpatialGrid>,
+    mut contacts: ResMut<Contacts>,
+    mut crushed: EventWriter<Crushed>,
+    mut static_queries: ParamSet<(
+        Query<(Entity, &mut Mover, &mut Transform, &StaticCollider)>,
+        Query<(&Transform, &StaticCollider)>,
+    )>,
+    mut body_query: Query<(Entity, &mut Transform, &DynamicCollider), Without<StaticCollider>>,
+) {
+    let mut moves = Vec::new();
+
+    for (entity, mut mover, mut transform, collider) in static_queries.p0().iter_mut() {
+        mover.waiting.tick(fixed_time.period);
+
+        if mover.waypoints.len() < 2 || !mover.waiting.finished() {
+            continue;
+        }
+
+        let before = Shape::new(collider.shape, collider.size, &transform);
+        let to_next = mover.waypoints[mover.next] - before.center();
+        let distance = mover.speed * fixed_time.period.as_secs_f32();
+
+        let displacement = if to_next.length() <= distance {
+            mover.advance();
+            to_next
+        } else {
+            to_next.normalize() * distance
+        };
+
+        transform.translation += displacement.extend(0.);
+        let after = before.translated(displacement);
+        grid.insert_static(entity, after.bounds());
+
+        moves.push(Move {
+            entity,
+            before,
+            after,
+            collider: *collider,
+        });
+    }
+
+    let static_query = static_queries.p1();
+
+    for Move {
+        entity: mover,
+        before,
+        after,
+        collider,
+    } in moves
+    {
+        // Sensors carry what was on them before the move, solid movers push
+        // what is in the way after it
+        let area = before.bounds().union(after.bounds());
+
+        for entity in grid.query_dynamic(area) {
+            let Ok((entity, mut transform, body_collider)) = body_query.get_mut(entity) else { continue; };
+
+            if !body_collider.layers.interacts(&collider.layers) {
+                continue;
+            }
+
+            let shape = Shape::new(body_collider.shape, body_collider.size, &transform);
+
+            let offset = if collider.sensor {
+                if penetration(&shape, &before).is_none() {
+                    continue;
+                }
+
+                after.center() - before.center()
+            } else {
+                let Some(hit) = penetration(&shape, &after) else { continue; };
+
+                if !collider.blocks(hit.normal) {
+                    continue;
+                }
+
+                contacts.swept.insert(
+                    (entity, mover),
+                    Contact {
+                        normal: hit.normal,
+                        kinds: (ColliderKind::Dynamic, ColliderKind::Static),
+                    },
+                );
+
+                hit.normal * (hit.depth + SKIN)
+            };
+
+            transform.translation += offset.extend(0.);
+            let shape = shape.translated(offset);
+            // Later movers this step have to find the body where it is now
+            grid.insert_dynamic(entity, shape.bounds());
+
+            // Pushed somewhere it can't be pushed out of again
+            let is_crushed = grid
+                .query_static(shape.bounds())
+                .into_iter()
+                .filter(|other| *other != mover)
+                .filter_map(|other| static_query.get(other).ok())
+                .filter(|(_, other_collider)| {
+                    !other_collider.sensor && body_collider.layers.interacts(&other_collider.layers)
+                })
+                .any(|(other_transform, other_collider)| {
+                    let other_shape =
+                        Shape::new(other_collider.shape, other_collider.size, other_transform);
+
+                    penetration(&shape, &other_shape).map_or(false, |hit| {
+                        hit.depth > CRUSH_DEPTH && other_collider.blocks(hit.normal)
+                    })
+                });
+
+            if is_crushed {
+                crushed.send(Crushed { entity });
+            }
+        }
+    }
+}
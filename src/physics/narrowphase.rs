@@ -0,0 +1,825 @@
+use bevy::prelude::*;
+
+use crate::components::ColliderShape;
+
+/// Iterations of the ternary search for the point of a capsule closest to a
+/// box. Each one shrinks the interval to two thirds.
+const CAPSULE_BOX_ITERATIONS: usize = 24;
+
+/// Collider geometry in world space. Circles are capsules whose segment has
+/// no length.
+#[derive(Clone, Copy, Debug)]
+pub enum Shape {
+    Aabb { center: Vec2, half_size: Vec2 },
+    Capsule { a: Vec2, b: Vec2, radius: f32 },
+}
+
+impl Shape {
+    /// Fits `shape` into a box of `size` centred on the transform. Circles
+    /// use the shorter side as diameter, capsules run along the longer side
+    /// and turn with the transform.
+    pub fn new(shape: ColliderShape, size: Vec2, transform: &Transform) -> Self {
+        let center = transform.translation.truncate();
+
+        match shape {
+            ColliderShape::Aabb => Shape::Aabb {
+                center,
+                half_size: size / 2.,
+            },
+            ColliderShape::Circle => Shape::Capsule {
+                a: center,
+                b: center,
+                radius: size.min_element() / 2.,
+            },
+            ColliderShape::Capsule => {
+                let radius = size.min_element() / 2.;
+                let half_length = size.max_element() / 2. - radius;
+                let axis = if size.x >= size.y { Vec3::X } else { Vec3::Y };
+                let axis = (transform.rotation * axis).truncate() * half_length;
+
+                Shape::Capsule {
+                    a: center - axis,
+                    b: center + axis,
+                    radius,
+                }
+            }
+        }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        match *self {
+            Shape::Aabb { center, .. } => center,
+            Shape::Capsule { a, b, .. } => (a + b) / 2.,
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        match *self {
+            Shape::Aabb { center, half_size } => Rect::from_center_half_size(center, half_size),
+            Shape::Capsule { a, b, radius } => {
+                Rect::from_corners(a.min(b) - radius, a.max(b) + radius)
+            }
+        }
+    }
+
+    pub fn translated(&self, offset: Vec2) -> Self {
+        match *self {
+            Shape::Aabb { center, half_size } => Shape::Aabb {
+                center: center + offset,
+                half_size,
+            },
+            Shape::Capsule { a, b, radius } => Shape::Capsule {
+                a: a + offset,
+                b: b + offset,
+                radius,
+            },
+        }
+    }
+
+    /// Half of the thinnest part of the shape, the furthest it can move in
+    /// one go without skipping over anything as thick as itself
+    pub fn min_half_extent(&self) -> f32 {
+        match *self {
+            Shape::Aabb { half_size, .. } => half_size.min_element(),
+            Shape::Capsule { radius, .. } => radius,
+        }
+    }
+}
+
+/// How far two shapes overlap. `normal` points from the second shape towards
+/// the first, moving the first shape by `normal * depth` separates them.
+#[derive(Clone, Copy, Debug)]
+pub struct Penetration {
+    pub normal: Vec2,
+    pub depth: f32,
+}
+
+impl Penetration {
+    fn flipped(self) -> Self {
+        Self {
+            normal: -self.normal,
+            depth: self.depth,
+        }
+    }
+}
+
+/// Overlap test between any two shapes, edges that only touch don't count
+pub fn penetration(shape: &Shape, other: &Shape) -> Option<Penetration> {
+    match (*shape, *other) {
+        (
+            Shape::Aabb { center, half_size },
+            Shape::Aabb {
+                center: other_center,
+                half_size: other_half_size,
+            },
+        ) => box_box(center, half_size, other_center, other_half_size),
+        (
+            Shape::Capsule { a, b, radius },
+            Shape::Capsule {
+                a: other_a,
+                b: other_b,
+                radius: other_radius,
+            },
+        ) => {
+            let (point, other_point) = closest_points_on_segments(a, b, other_a, other_b);
+            circle_circle(point, radius, other_point, other_radius)
+        }
+        (Shape::Capsule { a, b, radius }, Shape::Aabb { center, half_size }) => {
+            capsule_box(a, b, radius, center, half_size)
+        }
+        (Shape::Aabb { center, half_size }, Shape::Capsule { a, b, radius }) => {
+            capsule_box(a, b, radius, center, half_size).map(Penetration::flipped)
+        }
+    }
+}
+
+fn box_box(
+    center: Vec2,
+    half_size: Vec2,
+    other_center: Vec2,
+    other_half_size: Vec2,
+) -> Option<Penetration> {
+    let delta = center - other_center;
+    let overlap = half_size + other_half_size - delta.abs();
+
+    if overlap.x <= 0. || overlap.y <= 0. {
+        return None;
+    }
+
+    // Push out along the axis of least penetration
+    if overlap.x < overlap.y {
+        Some(Penetration {
+            normal: Vec2::new(delta.x.signum(), 0.),
+            depth: overlap.x,
+        })
+    } else {
+        Some(Penetration {
+            normal: Vec2::new(0., delta.y.signum()),
+            depth: overlap.y,
+        })
+    }
+}
+
+fn circle_circle(
+    center: Vec2,
+    radius: f32,
+    other_center: Vec2,
+    other_radius: f32,
+) -> Option<Penetration> {
+    let delta = center - other_center;
+    let distance = delta.length();
+    let depth = radius + other_radius - distance;
+
+    if depth <= 0. {
+        return None;
+    }
+
+    Some(Penetration {
+        normal: if distance > f32::EPSILON {
+            delta / distance
+        } else {
+            Vec2::Y
+        },
+        depth,
+    })
+}
+
+fn circle_box(center: Vec2, radius: f32, box_center: Vec2, half_size: Vec2) -> Option<Penetration> {
+    let local = center - box_center;
+    let delta = local - local.clamp(-half_size, half_size);
+
+    if delta == Vec2::ZERO {
+        // The centre is inside the box, push out through the closest side
+        let overlap = half_size - local.abs();
+
+        return Some(if overlap.x < overlap.y {
+            Penetration {
+                normal: Vec2::new(local.x.signum(), 0.),
+                depth: overlap.x + radius,
+            }
+        } else {
+            Penetration {
+                normal: Vec2::new(0., local.y.signum()),
+                depth: overlap.y + radius,
+            }
+        });
+    }
+
+    let distance = delta.length();
+
+    if distance >= radius {
+        return None;
+    }
+
+    Some(Penetration {
+        normal: delta / distance,
+        depth: radius - distance,
+    })
+}
+
+fn capsule_box(
+    a: Vec2,
+    b: Vec2,
+    radius: f32,
+    box_center: Vec2,
+    half_size: Vec2,
+) -> Option<Penetration> {
+    let distance_to_box = |point: Vec2| {
+        ((point - box_center).abs() - half_size)
+            .max(Vec2::ZERO)
+            .length()
+    };
+
+    // The distance from a point on the segment to the box is convex along
+    // the segment, so a ternary search finds the closest point
+    let (mut low, mut high) = (0., 1.);
+
+    for _ in 0..CAPSULE_BOX_ITERATIONS {
+        let first = low + (high - low) / 3.;
+        let second = high - (high - low) / 3.;
+
+        if distance_to_box(a.lerp(b, first)) <= distance_to_box(a.lerp(b, second)) {
+            high = second;
+        } else {
+            low = first;
+        }
+    }
+
+    circle_box(a.lerp(b, (low + high) / 2.), radius, box_center, half_size)
+}
+
+pub fn closest_point_on_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let segment = b - a;
+    let length_squared = segment.length_squared();
+
+    if length_squared <= f32::EPSILON {
+        return a;
+    }
+
+    a + segment * ((point - a).dot(segment) / length_squared).clamp(0., 1.)
+}
+
+/// Closest points between segments `a`-`b` and `c`-`d`, see Real-Time
+/// Collision Detection 5.1.9
+fn closest_points_on_segments(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> (Vec2, Vec2) {
+    let first = b - a;
+    let second = d - c;
+    let between = a - c;
+    let first_length = first.length_squared();
+    let second_length = second.length_squared();
+
+    if first_length <= f32::EPSILON {
+        return (a, closest_point_on_segment(a, c, d));
+    }
+
+    if second_length <= f32::EPSILON {
+        return (closest_point_on_segment(c, a, b), c);
+    }
+
+    let f = second.dot(between);
+    let c_dot = first.dot(between);
+    let b_dot = first.dot(second);
+    let denominator = first_length * second_length - b_dot * b_dot;
+
+    // Parallel segments have no unique closest pair, any point will do
+    let mut s = if denominator > f32::EPSILON {
+        ((b_dot * f - c_dot * second_length) / denominator).clamp(0., 1.)
+    } else {
+        0.
+    };
+    let mut t = (b_dot * s + f) / second_length;
+
+    if t < 0. {
+        t = 0.;
+        s = (-c_dot / first_length).clamp(0., 1.);
+    } else if t > 1. {
+        t = 1.;
+        s = ((b_dot - c_dot) / first_length).clamp(0., 1.);
+    }
+
+    (a + first * s, c + second * t)
+}
+
+/// Result of a sweep or ray test: how many times the displacement or ray
+/// direction was travelled before contact, and the normal of the surface
+/// that was hit.
+#[derive(Clone, Copy, Debug)]
+pub struct SweepHit {
+    pub time: f32,
+    pub normal: Vec2,
+}
+
+/// Sweeps a box centred on `origin` along `displacement` against a box
+/// centred on `target`. Only entering contacts are reported, a box that
+/// already overlaps the target is left alone so it can move out again.
+pub fn sweep_aabb(
+    origin: Vec2,
+    half_size: Vec2,
+    displacement: Vec2,
+    target: Vec2,
+    target_half_size: Vec2,
+) -> Option<SweepHit> {
+    // Sweeping a box against a box is a ray against the Minkowski sum
+    ray_aabb(origin, displacement, target, half_size + target_half_size)
+        .filter(|hit| hit.time <= 1.)
+}
+
+/// Casts a ray from `origin` along `direction` against a shape. `time` is
+/// measured in multiples of `direction`. Shapes that contain the origin are
+/// not hit, so rays can start inside the collider that casts them.
+pub fn raycast_shape(shape: &Shape, origin: Vec2, direction: Vec2) -> Option<SweepHit> {
+    match *shape {
+        Shape::Aabb { center, half_size } => ray_aabb(origin, direction, center, half_size),
+        Shape::Capsule { a, b, radius } => ray_capsule(origin, direction, a, b, radius),
+    }
+}
+
+fn ray_aabb(origin: Vec2, direction: Vec2, center: Vec2, half_size: Vec2) -> Option<SweepHit> {
+    if direction == Vec2::ZERO {
+        return None;
+    }
+
+    let min = center - half_size;
+    let max = center + half_size;
+
+    let mut entry = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+
+    // Slab test, the ray is inside the box where it is between both pairs of
+    // sides at once
+    for axis in 0..2 {
+        if direction[axis] == 0. {
+            if origin[axis] <= min[axis] || origin[axis] >= max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let inverse = 1. / direction[axis];
+        let (near, far, side) = if direction[axis] > 0. {
+            (min[axis], max[axis], -1.)
+        } else {
+            (max[axis], min[axis], 1.)
+        };
+        let near_time = (near - origin[axis]) * inverse;
+        let far_time = (far - origin[axis]) * inverse;
+
+        if near_time > entry {
+            entry = near_time;
+            normal = Vec2::ZERO;
+            normal[axis] = side;
+        }
+        exit = exit.min(far_time);
+    }
+
+    if entry > exit || entry < 0. {
+        return None;
+    }
+
+    Some(SweepHit {
+        time: entry,
+        normal,
+    })
+}
+
+fn ray_circle(origin: Vec2, direction: Vec2, center: Vec2, radius: f32) -> Option<SweepHit> {
+    let offset = origin - center;
+    let a = direction.length_squared();
+    let b = offset.dot(direction);
+    let c = offset.length_squared() - radius * radius;
+
+    // Starting inside or moving away
+    if c < 0. || b > 0. || a <= f32::EPSILON {
+        return None;
+    }
+
+    let discriminant = b * b - a * c;
+
+    if discriminant < 0. {
+        return None;
+    }
+
+    let time = (-b - discriminant.sqrt()) / a;
+
+    Some(SweepHit {
+        time,
+        normal: (offset + direction * time).normalize_or_zero(),
+    })
+}
+
+fn ray_capsule(origin: Vec2, direction: Vec2, a: Vec2, b: Vec2, radius: f32) -> Option<SweepHit> {
+    if origin.distance_squared(closest_point_on_segment(origin, a, b)) < radius * radius {
+        return None;
+    }
+
+    let caps = [a, b]
+        .into_iter()
+        .filter_map(|center| ray_circle(origin, direction, center, radius));
+
+    let segment = b - a;
+    let length = segment.length();
+
+    if length <= f32::EPSILON {
+        return caps.min_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    let axis = segment / length;
+
+    // The straight sides, only the one facing the ray can be entered
+    let sides = [1., -1.].into_iter().filter_map(|side| {
+        let normal = axis.perp() * side;
+        let approach = direction.dot(normal);
+
+        if approach >= 0. {
+            return None;
+        }
+
+        let time = (a + normal * radius - origin).dot(normal) / approach;
+        let along = (origin + direction * time - a).dot(axis);
+
+        (time >= 0. && (0. ..=length).contains(&along)).then_some(SweepHit { time, normal })
+    });
+
+    caps.chain(sides).min_by(|a, b| a.time.total_cmp(&b.time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(center: Vec2, size: Vec2) -> Shape {
+        Shape::Aabb {
+            center,
+            half_size: size / 2.,
+        }
+    }
+
+    fn circle(center: Vec2, radius: f32) -> Shape {
+        Shape::Capsule {
+            a: center,
+            b: center,
+            radius,
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn fast_box_does_not_tunnel_through_thin_wall() {
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::splat(8.),
+            Vec2::new(1000., 0.),
+            Vec2::new(500., 0.),
+            Vec2::new(0.25, 16.),
+        )
+        .unwrap();
+
+        assert_close(hit.time, (500. - 0.25 - 8.) / 1000.);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn box_slides_along_touching_wall() {
+        // The top of the box touches the bottom of the wall
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::splat(4.),
+            Vec2::new(40., 0.),
+            Vec2::new(20., 12.),
+            Vec2::new(16., 8.),
+        );
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn box_touching_wall_is_stopped_right_away() {
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::splat(4.),
+            Vec2::new(5., 0.),
+            Vec2::new(6., 0.),
+            Vec2::new(2., 10.),
+        )
+        .unwrap();
+
+        assert_close(hit.time, 0.);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn box_touching_wall_can_move_away() {
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::splat(4.),
+            Vec2::new(-5., 0.),
+            Vec2::new(6., 0.),
+            Vec2::new(2., 10.),
+        );
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn sweep_ignores_wall_it_already_overlaps() {
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::splat(4.),
+            Vec2::new(5., 0.),
+            Vec2::new(3., 0.),
+            Vec2::new(2., 10.),
+        );
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn sweep_too_short_to_reach_wall_misses() {
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::splat(4.),
+            Vec2::new(5., 0.),
+            Vec2::new(20., 0.),
+            Vec2::new(2., 10.),
+        );
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn wide_box_stops_at_thin_wall() {
+        // 16x4 box moving right, the 1 unit wall starts at x = 20
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::new(8., 2.),
+            Vec2::new(40., 0.),
+            Vec2::new(20.5, 0.),
+            Vec2::new(0.5, 16.),
+        )
+        .unwrap();
+
+        assert_close(hit.time, 12. / 40.);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn fast_thin_box_does_not_tunnel_through_thin_wall() {
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::new(2., 8.),
+            Vec2::new(1000., 0.),
+            Vec2::new(500., 0.),
+            Vec2::new(0.25, 4.),
+        )
+        .unwrap();
+
+        assert_close(hit.time, (500. - 0.25 - 2.) / 1000.);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn tall_box_hits_side_past_corner() {
+        // The 2x16 box reaches the corner at (12, 12) with its right side
+        // while it still overlaps the wall vertically
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::new(1., 8.),
+            Vec2::new(20., 20.),
+            Vec2::new(20., 20.),
+            Vec2::new(8., 8.),
+        )
+        .unwrap();
+
+        assert_close(hit.time, 0.55);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn wide_box_lands_on_corner_from_above() {
+        // The 32x2 box comes down onto the top right corner of the wall
+        let hit = sweep_aabb(
+            Vec2::new(20., 20.),
+            Vec2::new(16., 1.),
+            Vec2::new(0., -20.),
+            Vec2::ZERO,
+            Vec2::new(8., 8.),
+        )
+        .unwrap();
+
+        assert_close(hit.time, 11. / 20.);
+        assert_eq!(hit.normal, Vec2::Y);
+    }
+
+    #[test]
+    fn box_misses_corner_it_passes_beside() {
+        let hit = sweep_aabb(
+            Vec2::ZERO,
+            Vec2::new(1., 8.),
+            Vec2::new(40., 0.),
+            Vec2::new(20., 20.),
+            Vec2::new(8., 8.),
+        );
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn touching_boxes_do_not_penetrate() {
+        let hit = penetration(
+            &aabb(Vec2::ZERO, Vec2::new(16., 4.)),
+            &aabb(Vec2::new(16., 0.), Vec2::new(16., 4.)),
+        );
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn wide_box_is_pushed_out_of_thin_wall_along_shallow_axis() {
+        let hit = penetration(
+            &aabb(Vec2::ZERO, Vec2::new(16., 4.)),
+            &aabb(Vec2::new(8.5, 0.), Vec2::new(2., 40.)),
+        )
+        .unwrap();
+
+        assert_eq!(hit.normal, Vec2::NEG_X);
+        assert_close(hit.depth, 0.5);
+    }
+
+    #[test]
+    fn circle_is_pushed_away_from_corner() {
+        let hit = penetration(
+            &circle(Vec2::new(10., 10.), 5.),
+            &aabb(Vec2::ZERO, Vec2::splat(16.)),
+        )
+        .unwrap();
+
+        assert_close(hit.normal.x, std::f32::consts::FRAC_1_SQRT_2);
+        assert_close(hit.normal.y, std::f32::consts::FRAC_1_SQRT_2);
+        assert_close(hit.depth, 5. - 8_f32.sqrt());
+    }
+
+    #[test]
+    fn box_is_pushed_away_from_circle() {
+        let hit = penetration(
+            &aabb(Vec2::ZERO, Vec2::splat(16.)),
+            &circle(Vec2::new(10., 10.), 5.),
+        )
+        .unwrap();
+
+        assert_close(hit.normal.x, -std::f32::consts::FRAC_1_SQRT_2);
+        assert_close(hit.normal.y, -std::f32::consts::FRAC_1_SQRT_2);
+    }
+
+    #[test]
+    fn touching_circles_do_not_penetrate() {
+        let hit = penetration(&circle(Vec2::ZERO, 4.), &circle(Vec2::new(8., 0.), 4.));
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn circles_are_pushed_apart_along_centres() {
+        let hit = penetration(&circle(Vec2::ZERO, 5.), &circle(Vec2::new(6., 8.), 6.)).unwrap();
+
+        assert_close(hit.normal.x, -0.6);
+        assert_close(hit.normal.y, -0.8);
+        assert_close(hit.depth, 1.);
+    }
+
+    #[test]
+    fn parallel_capsules_are_pushed_apart() {
+        let capsule = Shape::Capsule {
+            a: Vec2::new(-10., 0.),
+            b: Vec2::new(10., 0.),
+            radius: 2.,
+        };
+        let other = Shape::Capsule {
+            a: Vec2::new(-4., 3.),
+            b: Vec2::new(16., 3.),
+            radius: 2.,
+        };
+        let hit = penetration(&capsule, &other).unwrap();
+
+        assert_close(hit.normal.x, 0.);
+        assert_close(hit.normal.y, -1.);
+        assert_close(hit.depth, 1.);
+    }
+
+    #[test]
+    fn capsule_end_is_pushed_out_of_crossing_capsule() {
+        // The end of the upright capsule sits just above the lying one
+        let capsule = Shape::Capsule {
+            a: Vec2::new(0., 1.),
+            b: Vec2::new(0., 10.),
+            radius: 2.,
+        };
+        let other = Shape::Capsule {
+            a: Vec2::new(-10., 0.),
+            b: Vec2::new(10., 0.),
+            radius: 2.,
+        };
+        let hit = penetration(&capsule, &other).unwrap();
+
+        assert_close(hit.normal.x, 0.);
+        assert_close(hit.normal.y, 1.);
+        assert_close(hit.depth, 3.);
+    }
+
+    #[test]
+    fn capsule_touching_thin_wall_does_not_penetrate() {
+        let capsule = Shape::Capsule {
+            a: Vec2::new(-6., 0.),
+            b: Vec2::new(6., 0.),
+            radius: 2.,
+        };
+
+        assert!(penetration(&capsule, &aabb(Vec2::new(0., 2.5), Vec2::new(40., 1.))).is_none());
+    }
+
+    #[test]
+    fn capsule_is_pushed_out_of_thin_wall() {
+        let capsule = Shape::Capsule {
+            a: Vec2::new(-6., 0.),
+            b: Vec2::new(6., 0.),
+            radius: 2.,
+        };
+        let hit = penetration(&capsule, &aabb(Vec2::new(0., 2.25), Vec2::new(40., 1.))).unwrap();
+
+        assert_close(hit.normal.x, 0.);
+        assert_close(hit.normal.y, -1.);
+        assert_close(hit.depth, 0.25);
+    }
+
+    #[test]
+    fn ray_hits_near_side_of_box() {
+        let hit = ray_aabb(Vec2::new(-10., 0.), Vec2::X, Vec2::ZERO, Vec2::new(2., 5.)).unwrap();
+
+        assert_close(hit.time, 8.);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn ray_along_box_edge_misses() {
+        let hit = ray_aabb(Vec2::new(-10., 5.), Vec2::X, Vec2::ZERO, Vec2::new(2., 5.));
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn ray_from_inside_box_misses() {
+        let hit = ray_aabb(Vec2::new(1., 1.), Vec2::X, Vec2::ZERO, Vec2::new(2., 5.));
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn ray_hits_side_of_capsule() {
+        let hit = ray_capsule(
+            Vec2::new(0., 10.),
+            Vec2::NEG_Y,
+            Vec2::new(-5., 0.),
+            Vec2::new(5., 0.),
+            1.,
+        )
+        .unwrap();
+
+        assert_close(hit.time, 9.);
+        assert_eq!(hit.normal, Vec2::Y);
+    }
+
+    #[test]
+    fn ray_hits_end_of_capsule() {
+        let hit = ray_capsule(
+            Vec2::new(-20., 0.),
+            Vec2::X,
+            Vec2::new(-5., 0.),
+            Vec2::new(5., 0.),
+            1.,
+        )
+        .unwrap();
+
+        assert_close(hit.time, 14.);
+        assert_close(hit.normal.x, -1.);
+        assert_close(hit.normal.y, 0.);
+    }
+
+    #[test]
+    fn ray_from_inside_capsule_misses() {
+        let hit = ray_capsule(
+            Vec2::new(0., 0.5),
+            Vec2::Y,
+            Vec2::new(-5., 0.),
+            Vec2::new(5., 0.),
+            1.,
+        );
+
+        assert!(hit.is_none());
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use super::narrowphase::{penetration, raycast_shape, Shape};
+use super::SpatialGrid;
+use crate::components::*;
+
+/// The closest collider a ray ran into
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub entity: Entity,
+    pub point: Vec2,
+    /// Normal of the surface that was hit, pointing back towards the ray
+    pub normal: Vec2,
+    pub distance: f32,
+}
+
+/// Read-only access to the colliders in the world, for systems that need to
+/// ask about them outside of collision events. Dynamic colliders are only
+/// up to date after [`super::PhysicsSet::Broadphase`].
+#[derive(SystemParam)]
+pub struct PhysicsQuery<'w, 's> {
+    grid: Res<'w, SpatialGrid>,
+    static_collider_query: Query<'w, 's, (&'static Transform, &'static StaticCollider)>,
+    dynamic_collider_query: Query<'w, 's, (&'static Transform, &'static DynamicCollider)>,
+}
+
+impl<'w, 's> PhysicsQuery<'w, 's> {
+    /// Casts a ray from `origin` along `direction` and returns the closest
+    /// collider within `max_distance` that interacts with `layers` and for
+    /// which `filter` returns true. Colliders containing `origin` are never
+    /// hit, and neither are sensors or one-way walls seen from the side they
+    /// let through.
+    pub fn raycast(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+        layers: CollisionLayers,
+        filter: impl Fn(Entity) -> bool,
+    ) -> Option<RayHit> {
+        let direction = direction.normalize_or_zero();
+
+        if direction == Vec2::ZERO {
+            return None;
+        }
+
+        let end = origin + direction * max_distance;
+
+        let static_hits = self
+            .grid
+            .query_static_segment(origin, end)
+            .into_iter()
+            .filter(|entity| filter(*entity))
+            .filter_map(|entity| {
+                let (transform, collider) = self.static_collider_query.get(entity).ok()?;
+
+                if !layers.interacts(&collider.layers) {
+                    return None;
+                }
+
+                let shape = Shape::new(collider.shape, collider.size, transform);
+                raycast_shape(&shape, origin, direction)
+                    .filter(|hit| collider.blocks(hit.normal))
+                    .map(|hit| (entity, hit))
+            });
+
+        let dynamic_hits = self
+            .grid
+            .query_dynamic_segment(origin, end)
+            .into_iter()
+            .filter(|entity| filter(*entity))
+            .filter_map(|entity| {
+                let (transform, collider) = self.dynamic_collider_query.get(entity).ok()?;
+
+                if !layers.interacts(&collider.layers) {
+                    return None;
+                }
+
+                let shape = Shape::new(collider.shape, collider.size, transform);
+                raycast_shape(&shape, origin, direction).map(|hit| (entity, hit))
+            });
+
+        let (entity, hit) = static_hits
+            .chain(dynamic_hits)
+            .filter(|(_, hit)| hit.time <= max_distance)
+            .min_by(|(_, a), (_, b)| a.time.total_cmp(&b.time))?;
+
+        Some(RayHit {
+            entity,
+            point: origin + direction * hit.time,
+            normal: hit.normal,
+            distance: hit.time,
+        })
+    }
+
+    /// Whether the static collider `entity` overlaps a dynamic collider it
+    /// interacts with. Sensors count too, colliders merely touching don't.
+    pub fn overlaps_dynamic(&self, entity: Entity) -> bool {
+        let Ok((transform, collider)) = self.static_collider_query.get(entity) else { return false; };
+        let shape = Shape::new(collider.shape, collider.size, transform);
+
+        self.grid
+            .query_dynamic(shape.bounds())
+            .into_iter()
+            .filter_map(|other| self.dynamic_collider_query.get(other).ok())
+            .filter(|(_, other_collider)| collider.layers.interacts(&other_collider.layers))
+            .any(|(other_transform, other_collider)| {
+                let other_shape =
+                    Shape::new(other_collider.shape, other_collider.size, other_transform);
+                penetration(&shape, &other_shape).is_some()
+            })
+    }
+}
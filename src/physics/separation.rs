@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use super::contacts::{ColliderKind, Contact, Contacts};
+use super::narrowphase::{penetration, Shape};
+use super::{SpatialGrid, SKIN};
+use crate::components::*;
+
+/// Rounds of pushing solid bodies apart per simulation step. Pushing one
+/// pair apart can shove a body into a third one, more rounds settle crowds.
+const SEPARATION_ITERATIONS: usize = 4;
+
+struct Body {
+    entity: Entity,
+    shape: Shape,
+    start: Vec2,
+    inverse_mass: f32,
+    layers: CollisionLayers,
+}
+
+/// Pushes overlapping solid bodies apart, split by their inverse mass, and
+/// keeps them out of static colliders while doing so. Solid bodies rarely
+/// number more than a handful, so every pair is simply tested.
+pub(super) fn separate_solid_bodies(
+    grid: Res<SpatialGrid>,
+    mut contacts: ResMut<Contacts>,
+    static_collider_query: Query<(&Transform, &StaticCollider)>,
+    mut solid_query: Query<
+        (Entity, &mut Transform, &DynamicCollider, &Solid),
+        Without<StaticCollider>,
+    >,
+) {
+    let mut bodies: Vec<Body> = solid_query
+        .iter()
+        .map(|(entity, transform, collider, solid)| {
+            let shape = Shape::new(collider.shape, collider.size, transform);
+
+            Body {
+                entity,
+                shape,
+                start: shape.center(),
+                inverse_mass: solid.inverse_mass(),
+                layers: collider.layers,
+            }
+        })
+        .collect();
+
+    // Matches the order `detect_collisions` visits dynamic pairs in
+    bodies.sort_unstable_by_key(|body| body.entity);
+
+    for _ in 0..SEPARATION_ITERATIONS {
+        let mut separated = false;
+
+        for i in 0..bodies.len() {
+            for j in i + 1..bodies.len() {
+                let total_inverse_mass = bodies[i].inverse_mass + bodies[j].inverse_mass;
+
+                if total_inverse_mass <= 0. || !bodies[i].layers.interacts(&bodies[j].layers) {
+                    continue;
+                }
+
+                let Some(hit) = penetration(&bodies[i].shape, &bodies[j].shape) else { continue; };
+
+                // Bodies that were pushed apart no longer overlap, so
+                // `detect_collisions` wouldn't notice they touched
+                contacts.swept.insert(
+                    (bodies[i].entity, bodies[j].entity),
+                    Contact {
+                        normal: hit.normal,
+                        kinds: (ColliderKind::Dynamic, ColliderKind::Dynamic),
+                    },
+                );
+
+                let push = hit.normal * (hit.depth + SKIN) / total_inverse_mass;
+                bodies[i].shape = bodies[i].shape.translated(push * bodies[i].inverse_mass);
+                bodies[j].shape = bodies[j].shape.translated(-push * bodies[j].inverse_mass);
+                separated = true;
+            }
+        }
+
+        if !separated {
+            break;
+        }
+
+        // Walls don't give way, whoever got pushed into one is pushed back
+        for body in bodies.iter_mut() {
+            for other in grid.query_static(body.shape.bounds()) {
+                let Ok((other_transform, other_collider)) = static_collider_query.get(other) else { continue; };
+                let other_shape =
+                    Shape::new(other_collider.shape, other_collider.size, other_transform);
+                let Some(hit) = penetration(&body.shape, &other_shape) else { continue; };
+
+                if !other_collider.blocks(hit.normal)
+                    || !body.layers.interacts(&other_collider.layers)
+                {
+                    continue;
+                }
+
+                contacts.swept.insert(
+                    (body.entity, other),
+                    Contact {
+                        normal: hit.normal,
+                        kinds: (ColliderKind::Dynamic, ColliderKind::Static),
+                    },
+                );
+
+                body.shape = body.shape.translated(hit.normal * (hit.depth + SKIN));
+            }
+        }
+    }
+
+    for body in bodies {
+        let offset = body.shape.center() - body.start;
+
+        if offset == Vec2::ZERO {
+            continue;
+        }
+
+        let Ok((_, mut transform, _, _)) = solid_query.get_mut(body.entity) else { continue; };
+        transform.translation.x += offset.x;
+        transform.translation.y += offset.y;
+    }
+}
@@ -3,13 +3,17 @@ use std::time::Duration;
 use crate::actions::Actions;
 use crate::components::*;
 use crate::config::*;
+use crate::enemy::DeathCause;
+use crate::enemy::EnemyDied;
 use crate::enemy::Projectile;
 use crate::environment::Goal;
 use crate::loading::AudioAssets;
 use crate::loading::TextureAssets;
+use crate::physics::CollisionStarted;
+use crate::physics::Crushed;
+use crate::physics::PhysicsSet;
 use crate::GameState;
 use bevy::prelude::*;
-use bevy::sprite::collide_aabb::collide;
 use bevy_ecs_ldtk::LevelSelection;
 use bevy_kira_audio::Audio;
 use bevy_kira_audio::AudioControl;
@@ -27,17 +31,18 @@ impl Plugin for PlayerPlugin {
                 Duration::from_secs_f32(FOOTSTEP_INTERVAL),
                 TimerMode::Repeating,
             )))
-            .insert_resource(PlayerVelocityHistory::new(HISTORY_LENGTH))
+            .insert_resource(PlayerHistory::new(HISTORY_LENGTH))
+            .add_systems((footsteps, set_level, count_kills).in_set(OnUpdate(GameState::Playing)))
             .add_systems(
                 (
-                    footsteps,
-                    update_velocity,
+                    update_velocity.before(PhysicsSet::Movement),
                     rotate,
-                    set_level,
-                    level_complete,
-                    player_hit,
+                    record_history.after(PhysicsSet::Movement),
+                    level_complete.after(PhysicsSet::Broadphase),
+                    player_hit.after(PhysicsSet::Broadphase),
                 )
-                    .in_set(OnUpdate(GameState::Playing)),
+                    .distributive_run_if(in_state(GameState::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
             );
     }
 }
@@ -50,20 +55,26 @@ pub struct Player {
 #[derive(Bundle)]
 pub struct PlayerBundle {
     player: Player,
+    spawn_point: SpawnPoint,
     velocity: Velocity,
+    previous_translation: PreviousTranslation,
     collider: DynamicCollider,
     sprite_bundle: SpriteBundle,
 }
 
 impl PlayerBundle {
-    pub fn new(position: Vec3) -> Self {
+    pub fn new(position: Vec3, shape: ColliderShape) -> Self {
         Self {
             player: Player {
                 used_left_foot: false,
             },
+            spawn_point: SpawnPoint(position.truncate()),
             velocity: Velocity::default(),
+            previous_translation: PreviousTranslation(position),
             collider: DynamicCollider {
                 size: Vec2::splat(UNIT),
+                shape,
+                layers: CollisionLayers::new(Layers::PLAYER, Layers::ALL),
             },
             sprite_bundle: SpriteBundle {
                 sprite: Sprite {
@@ -81,6 +92,7 @@ impl PlayerBundle {
 #[derive(Resource, Default)]
 pub struct PlayerState {
     pub hp: f32,
+    pub score: u32,
 }
 
 #[derive(Resource)]
@@ -94,29 +106,47 @@ pub struct Footstep {
 
 pub struct FootstepEvent;
 
+/// Where the player was at the end of a simulation step
+#[derive(Clone, Copy, Default)]
+pub struct HistoryEntry {
+    pub position: Vec2,
+    pub heading: Quat,
+    pub velocity: Vec3,
+}
+
+/// The last `size` simulation steps of the player, echoed by the enemies
 #[derive(Resource)]
-pub struct PlayerVelocityHistory {
-    velocities: Vec<Vec3>,
+pub struct PlayerHistory {
+    entries: Vec<HistoryEntry>,
     size: usize,
     pointer: usize,
+    recorded: usize,
 }
 
-impl PlayerVelocityHistory {
+impl PlayerHistory {
     fn new(size: usize) -> Self {
         Self {
-            velocities: vec![Vec3::ZERO; size],
-            size: size,
+            entries: vec![HistoryEntry::default(); size],
+            size,
             pointer: 0,
+            recorded: 0,
         }
     }
 
-    pub fn get(&mut self, offset: usize) -> Vec3 {
-        self.velocities[(self.pointer + 1 + offset) % self.size]
+    /// The entry recorded `steps` simulation steps ago, nothing if the
+    /// player hasn't been around that long
+    pub fn get(&self, steps: usize) -> Option<&HistoryEntry> {
+        if steps >= self.recorded.min(self.size) {
+            return None;
+        }
+
+        Some(&self.entries[(self.pointer + self.size - 1 - steps) % self.size])
     }
 
-    fn set(&mut self, velocity: Vec3) {
-        self.velocities[self.pointer] = velocity;
+    fn push(&mut self, entry: HistoryEntry) {
+        self.entries[self.pointer] = entry;
         self.pointer = (self.pointer + 1) % self.size;
+        self.recorded += 1;
     }
 }
 
@@ -130,7 +160,8 @@ fn footsteps(
     time: Res<Time>,
 ) {
     if let Ok((mut player, player_transform, player_velocity)) = player_query.get_single_mut() {
-        let player_speed = player_velocity.0.length();
+        // Distance covered per simulation step
+        let player_speed = player_velocity.0.length() * TIMESTEP;
 
         // Distance between footsteps
         let interval = FOOTSTEP_INTERVAL * (15. - player_speed).max(0.11);
@@ -183,53 +214,67 @@ fn footsteps(
 }
 
 fn level_complete(
-    goal_query: Query<&Transform, With<Goal>>,
-    mut player_query: Query<(&Transform, &mut Velocity), With<Player>>,
+    mut collisions: EventReader<CollisionStarted>,
+    goal_query: Query<(), With<Goal>>,
+    mut player_query: Query<(Entity, &mut Velocity), With<Player>>,
     mut state: ResMut<NextState<GameState>>,
     audio_assets: Res<AudioAssets>,
     audio: Res<Audio>,
-    mut player_velocity_history: ResMut<PlayerVelocityHistory>,
+    mut player_history: ResMut<PlayerHistory>,
 ) {
-    if let Ok((player, mut velocity)) = player_query.get_single_mut() {
-        if let Ok(goal) = goal_query.get_single() {
-            let distance = goal.translation - player.translation;
-
-            if distance.length() < UNIT * 2. {
-                velocity.0 = Vec3::ZERO;
-                *player_velocity_history = PlayerVelocityHistory::new(50);
-                state.set(GameState::LevelComplete);
-
-                audio
-                    .play(audio_assets.level_complete.clone())
-                    .with_volume(0.1);
-            }
+    let Ok((player, mut velocity)) = player_query.get_single_mut() else { return; };
+
+    for collision in collisions.iter() {
+        let Some(other) = collision.other(player) else { continue; };
+
+        if goal_query.contains(other) {
+            velocity.0 = Vec3::ZERO;
+            *player_history = PlayerHistory::new(HISTORY_LENGTH);
+            state.set(GameState::LevelComplete);
+
+            audio
+                .play(audio_assets.level_complete.clone())
+                .with_volume(0.1);
+
+            break;
         }
     }
 }
 
 fn update_velocity(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
     actions: Res<Actions>,
-    mut player_velocity_history: ResMut<PlayerVelocityHistory>,
     mut player_velocity_query: Query<&mut Velocity, With<Player>>,
 ) {
+    let delta = fixed_time.period.as_secs_f32();
     let mut input = Vec3::ZERO;
     let mut acceleration = DECELERATION;
 
     if actions.player_movement.is_some() {
         acceleration = ACCELERATION;
         input = Vec3::new(
-            actions.player_movement.unwrap().x * SPEED * time.delta_seconds(),
-            actions.player_movement.unwrap().y * SPEED * time.delta_seconds(),
+            actions.player_movement.unwrap().x * SPEED,
+            actions.player_movement.unwrap().y * SPEED,
             0.,
         );
     }
 
     if let Ok(mut player_velocity) = player_velocity_query.get_single_mut() {
         let velocity_difference = input - player_velocity.0;
-        player_velocity.0 += velocity_difference * acceleration * time.delta_seconds();
+        player_velocity.0 += velocity_difference * acceleration * delta;
+    }
+}
 
-        player_velocity_history.set(player_velocity.0);
+fn record_history(
+    mut player_history: ResMut<PlayerHistory>,
+    player_query: Query<(&Transform, &Velocity), With<Player>>,
+) {
+    if let Ok((transform, velocity)) = player_query.get_single() {
+        player_history.push(HistoryEntry {
+            position: transform.translation.truncate(),
+            heading: transform.rotation,
+            velocity: velocity.0,
+        });
     }
 }
 
@@ -242,36 +287,57 @@ fn rotate(mut player_query: Query<(&mut Transform, &Velocity), With<Player>>) {
 }
 
 fn player_hit(
-    projectile_query: Query<(&Transform, &DynamicCollider), With<Projectile>>,
-    mut player_query: Query<(&Transform, &DynamicCollider, &mut Velocity), With<Player>>,
+    mut collisions: EventReader<CollisionStarted>,
+    mut crushed: EventReader<Crushed>,
+    projectile_query: Query<(), With<Projectile>>,
+    player_query: Query<Entity, With<Player>>,
     mut level_selection: ResMut<LevelSelection>,
     mut events: EventWriter<SetLevelEvent>,
     audio_assets: Res<AudioAssets>,
     audio: Res<Audio>,
 ) {
-    for (player_transform, player_collider, mut velocity) in player_query.iter_mut() {
-        for (projectile_transform, projectile_collider) in projectile_query.iter() {
-            if collide(
-                player_transform.translation,
-                player_collider.size,
-                projectile_transform.translation,
-                projectile_collider.size,
-            )
-            .is_some()
-            {
-                audio.play(audio_assets.hit_hurt.clone()).with_volume(0.2);
-
-                match *level_selection {
-                    LevelSelection::Index(i) => events.send(SetLevelEvent(i)),
-                    _ => {}
-                }
-
-                match *level_selection {
-                    LevelSelection::Index(i) => *level_selection = LevelSelection::Index(0),
-                    _ => {}
-                }
-            }
-        }
+    let was_shot = collisions.iter().any(|collision| {
+        player_query.iter().any(|player| {
+            collision
+                .other(player)
+                .map_or(false, |other| projectile_query.contains(other))
+        })
+    });
+
+    // Being crushed by a moving wall is as deadly as being shot
+    let was_crushed = crushed
+        .iter()
+        .any(|crushed| player_query.contains(crushed.entity));
+
+    // `any` stops at the first hit, the events after it would hit the player
+    // again once the level is reloaded
+    collisions.clear();
+    crushed.clear();
+
+    if !was_shot && !was_crushed {
+        return;
+    }
+
+    audio.play(audio_assets.hit_hurt.clone()).with_volume(0.2);
+
+    match *level_selection {
+        LevelSelection::Index(i) => events.send(SetLevelEvent(i)),
+        _ => {}
+    }
+
+    match *level_selection {
+        LevelSelection::Index(i) => *level_selection = LevelSelection::Index(0),
+        _ => {}
+    }
+}
+
+/// Tricking an enemy into shooting another is worth more than a squash
+fn count_kills(mut events: EventReader<EnemyDied>, mut player_state: ResMut<PlayerState>) {
+    for event in events.iter() {
+        player_state.score += match event.cause {
+            DeathCause::Projectile => 2,
+            DeathCause::Squash | DeathCause::Hazard => 1,
+        };
     }
 }
 
@@ -281,14 +347,14 @@ fn set_level(
     mut velocity: Query<&mut Velocity, With<Player>>,
     mut events: EventReader<SetLevelEvent>,
     mut level_selection: ResMut<LevelSelection>,
-    mut player_velocity_history: ResMut<PlayerVelocityHistory>,
+    mut player_history: ResMut<PlayerHistory>,
 ) {
     for e in events.iter() {
         for mut velocity in velocity.iter_mut() {
             velocity.0 = Vec3::ZERO;
         }
 
-        *player_velocity_history = PlayerVelocityHistory::new(HISTORY_LENGTH);
+        *player_history = PlayerHistory::new(HISTORY_LENGTH);
 
         *level_selection = LevelSelection::Index(e.0);
     }
@@ -22,6 +22,7 @@ use bevy::{
     sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
 };
 
+use crate::camera::CameraController;
 use crate::config::*;
 
 pub struct PostProcessingPlugin;
@@ -37,6 +38,10 @@ impl Plugin for PostProcessingPlugin {
 #[derive(Component)]
 struct MainCube;
 
+/// The camera looking at the level, as opposed to the post processing pass
+#[derive(Component)]
+pub struct MainCamera;
+
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -73,16 +78,14 @@ fn setup(
 
     // Main camera, first to render
     commands.spawn((
+        MainCamera,
+        CameraController::default(),
         Camera2dBundle {
             camera: Camera {
                 target: RenderTarget::Image(image_handle.clone()),
                 ..default()
             },
-            transform: Transform::from_translation(Vec3 {
-                x: WINDOW_WIDTH / 2. + UNIT,
-                y: WINDOW_HEIGHT / 2. + UNIT,
-                z: 999.,
-            }),
+            transform: Transform::from_translation(Vec3::Z * 999.),
             ..default()
         },
         // Disable UI rendering for the first pass camera. This prevents double rendering of UI at